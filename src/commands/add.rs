@@ -1,7 +1,52 @@
-use crate::{config::Config, error::NrsError};
+use colored::Colorize;
+use serde::Serialize;
 
-pub fn execute(mut config: Config, name: &str, url: &str) -> Result<(), NrsError> {
-    config.add_registry(name, url)?;
-    println!("Added registry: {} ({})", name, url);
+use crate::{
+    config::{AddOutcome, Config},
+    error::NrsError,
+    output::{OutputFormat, print_json},
+};
+
+#[derive(Serialize)]
+struct AddReport<'a> {
+    action: &'static str,
+    name: &'a str,
+    url: &'a str,
+    added: bool,
+    existing: Option<String>,
+}
+
+pub fn execute(
+    mut config: Config,
+    name: &str,
+    url: &str,
+    format: OutputFormat,
+) -> Result<(), NrsError> {
+    let outcome = config.add_registry(name, url)?;
+    if format.is_json() {
+        let existing = match &outcome {
+            AddOutcome::Added => None,
+            AddOutcome::Exists(existing) => Some(existing.clone()),
+        };
+        return print_json(&AddReport {
+            action: "add",
+            name,
+            url,
+            added: existing.is_none(),
+            existing,
+        });
+    }
+    match outcome {
+        AddOutcome::Added => println!("Added registry: {} ({})", name, url),
+        AddOutcome::Exists(existing) if existing == name => {
+            println!("Registry {} already exists ({})", name, url)
+        }
+        AddOutcome::Exists(existing) => println!(
+            "{} Registry URL {} already exists as {}. Use that name or edit it.",
+            "Warning:".yellow(),
+            url,
+            existing
+        ),
+    }
     Ok(())
 }
@@ -0,0 +1,46 @@
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::{
+    config::{AddOutcome, Config},
+    error::NrsError,
+    output::{OutputFormat, print_json},
+};
+
+#[derive(Serialize)]
+struct AdoptReport<'a> {
+    action: &'static str,
+    name: &'a str,
+    url: Option<String>,
+    adopted: bool,
+    existing: Option<String>,
+}
+
+/// Copies a registry defined by the project into `~/.nrsrc`.
+pub fn execute(mut config: Config, name: &str, format: OutputFormat) -> Result<(), NrsError> {
+    let outcome = config.adopt_registry(name)?;
+    let url = config.get_url(name).cloned();
+    let existing = match &outcome {
+        AddOutcome::Added => None,
+        AddOutcome::Exists(existing) => Some(existing.clone()),
+    };
+    if format.is_json() {
+        return print_json(&AdoptReport {
+            action: "adopt",
+            name,
+            url,
+            adopted: existing.is_none(),
+            existing,
+        });
+    }
+    match existing {
+        None => println!("Adopted registry: {} ({})", name, url.unwrap_or_default()),
+        Some(existing) => println!(
+            "{} Registry URL {} already exists as {}. Use that name or edit it.",
+            "Warning:".yellow(),
+            url.unwrap_or_default(),
+            existing
+        ),
+    }
+    Ok(())
+}
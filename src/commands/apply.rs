@@ -0,0 +1,75 @@
+use std::env;
+
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::{
+    config::Config,
+    error::NrsError,
+    output::{OutputFormat, print_json},
+    pin::Pin,
+};
+
+#[derive(Serialize)]
+struct PinnedRegistry {
+    name: String,
+    url: String,
+}
+
+#[derive(Serialize)]
+struct PinnedScope {
+    scope: String,
+    registry: String,
+    url: String,
+}
+
+#[derive(Serialize)]
+struct ApplyReport {
+    action: &'static str,
+    pin: String,
+    npmrc: String,
+    registry: Option<PinnedRegistry>,
+    scopes: Vec<PinnedScope>,
+}
+
+/// Finds the nearest pin file above the working directory and writes it to
+/// the `.npmrc` beside it.
+pub fn execute(config: Config, backup: bool, format: OutputFormat) -> Result<(), NrsError> {
+    let cwd = env::current_dir()?;
+    let path = Pin::find(&cwd).ok_or(NrsError::PinNotFound(cwd))?;
+    let pin = Pin::load(&path)?;
+    let dir = path.parent().unwrap_or(&path);
+    let npmrc_path = dir.join(".npmrc");
+    config.apply_pin(&pin, &npmrc_path, backup)?;
+
+    let url = |name: &str| config.get_url(name).cloned().unwrap_or_default();
+    let report = ApplyReport {
+        action: "apply",
+        pin: path.display().to_string(),
+        npmrc: npmrc_path.display().to_string(),
+        registry: pin.registry.as_ref().map(|name| PinnedRegistry {
+            name: name.clone(),
+            url: url(name),
+        }),
+        scopes: pin
+            .scopes
+            .iter()
+            .map(|(scope, name)| PinnedScope {
+                scope: scope.clone(),
+                registry: name.clone(),
+                url: url(name),
+            })
+            .collect(),
+    };
+    if format.is_json() {
+        return print_json(&report);
+    }
+    println!("Applied {} to {}", report.pin, report.npmrc);
+    if let Some(registry) = &report.registry {
+        println!("  registry: {} ({})", registry.name.green(), registry.url);
+    }
+    for scope in &report.scopes {
+        println!("  {}: {} ({})", scope.scope, scope.registry, scope.url);
+    }
+    Ok(())
+}
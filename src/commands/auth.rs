@@ -0,0 +1,116 @@
+use clap::{Subcommand, ValueHint};
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::{
+    config::{AuthToken, Config},
+    error::NrsError,
+    output::{OutputFormat, print_json},
+    utils::auth_key,
+};
+
+#[derive(Subcommand)]
+pub enum AuthAction {
+    /// Store an auth token for a registry
+    Set {
+        #[arg(value_hint = ValueHint::Other)]
+        name: String,
+        /// Literal token, stored in ~/.nrsrc
+        #[arg(
+            long,
+            required_unless_present = "token_env",
+            conflicts_with = "token_env"
+        )]
+        token: Option<String>,
+        /// Environment variable npm expands at runtime, e.g. NPM_TOKEN
+        #[arg(long)]
+        token_env: Option<String>,
+    },
+    /// List registries with stored credentials
+    Ls,
+    /// Remove stored credentials for a registry
+    Rm {
+        #[arg(value_hint = ValueHint::Other)]
+        name: String,
+    },
+}
+
+/// Never carries the token itself, only its masked form.
+#[derive(Serialize)]
+struct AuthReport<'a> {
+    action: &'static str,
+    name: &'a str,
+    key: Option<String>,
+    token: Option<String>,
+}
+
+pub fn execute(
+    mut config: Config,
+    action: AuthAction,
+    format: OutputFormat,
+) -> Result<(), NrsError> {
+    match action {
+        AuthAction::Set {
+            name,
+            token,
+            token_env,
+        } => {
+            let token = match (token, token_env) {
+                (Some(token), _) => AuthToken::Token(token),
+                (None, Some(var)) => AuthToken::Env(var.trim_start_matches('$').to_string()),
+                (None, None) => unreachable!("clap requires --token or --token-env"),
+            };
+            let masked = token.masked();
+            config.set_auth(&name, token)?;
+            if format.is_json() {
+                return print_json(&AuthReport {
+                    action: "set",
+                    name: &name,
+                    key: config.get_url(&name).map(|url| auth_key(url)),
+                    token: Some(masked),
+                });
+            }
+            println!("Stored credentials for {} ({})", name.green(), masked);
+        }
+        AuthAction::Ls => {
+            let mut entries: Vec<_> = config.auth.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            if format.is_json() {
+                let reports: Vec<_> = entries
+                    .iter()
+                    .map(|(name, token)| AuthReport {
+                        action: "ls",
+                        name,
+                        key: config.get_url(name).map(|url| auth_key(url)),
+                        token: Some(token.masked()),
+                    })
+                    .collect();
+                return print_json(&reports);
+            }
+            if entries.is_empty() {
+                println!("{}", "No credentials stored".yellow());
+                return Ok(());
+            }
+            for (name, token) in entries {
+                let key = config
+                    .get_url(name)
+                    .map(|url| auth_key(url))
+                    .unwrap_or_else(|| "(unknown registry)".to_string());
+                println!("{:15} {} {}", name, key, token.masked().dimmed());
+            }
+        }
+        AuthAction::Rm { name } => {
+            config.remove_auth(&name)?;
+            if format.is_json() {
+                return print_json(&AuthReport {
+                    action: "rm",
+                    name: &name,
+                    key: None,
+                    token: None,
+                });
+            }
+            println!("Removed credentials for {}", name);
+        }
+    }
+    Ok(())
+}
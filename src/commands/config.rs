@@ -0,0 +1,99 @@
+use std::fs;
+
+use clap::Subcommand;
+use colored::Colorize;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    config::Config,
+    error::NrsError,
+    migrate::{CURRENT_VERSION, backup_path, version_of},
+    output::{OutputFormat, print_json},
+    utils::get_config_path,
+};
+
+#[derive(Subcommand)]
+pub enum ConfigAction {
+    /// Upgrade ~/.nrsrc to the current schema, keeping a backup of the old file
+    Migrate {
+        /// Only report whether a migration is needed; exits non-zero if it is
+        #[arg(long)]
+        check: bool,
+    },
+}
+
+#[derive(Serialize)]
+struct MigrateReport {
+    action: &'static str,
+    path: String,
+    from: u32,
+    to: u32,
+    /// True when the file is (or, with `--check`, would be) rewritten.
+    pending: bool,
+    check: bool,
+    backup: Option<String>,
+}
+
+pub fn execute(config: Config, action: ConfigAction, format: OutputFormat) -> Result<(), NrsError> {
+    match action {
+        ConfigAction::Migrate { check } => migrate_file(config, check, format),
+    }
+}
+
+/// `config` was already upgraded in memory by [`Config::load`]; saving it
+/// backs up and rewrites the file.
+fn migrate_file(config: Config, check: bool, format: OutputFormat) -> Result<(), NrsError> {
+    let config_path = get_config_path()?;
+    let value: Option<Value> = match fs::read_to_string(&config_path) {
+        Ok(contents) => Some(serde_json::from_str(&contents)?),
+        Err(_) => None,
+    };
+    let from = value.as_ref().map_or(CURRENT_VERSION, version_of);
+    let mut report = MigrateReport {
+        action: "migrate",
+        path: config_path.display().to_string(),
+        from,
+        to: CURRENT_VERSION,
+        pending: from < CURRENT_VERSION,
+        check,
+        backup: None,
+    };
+
+    if !check && report.pending {
+        config.save()?;
+        report.backup = Some(backup_path(from)?.display().to_string());
+    }
+
+    if format.is_json() {
+        print_json(&report)?;
+    } else if !report.pending {
+        println!(
+            "{} ~/.nrsrc is at schema version {}",
+            "Up to date:".green(),
+            CURRENT_VERSION
+        );
+    } else if check {
+        println!(
+            "{} ~/.nrsrc is at schema version {}, current is {}",
+            "Migration needed:".yellow(),
+            from,
+            CURRENT_VERSION
+        );
+    } else {
+        println!(
+            "Migrated ~/.nrsrc from schema version {} to {}",
+            from, CURRENT_VERSION
+        );
+        if let Some(backup) = &report.backup {
+            println!("Backup of the old file: {}", backup);
+        }
+    }
+
+    // The report already says a migration is pending; going through the error
+    // path would print a second document after the JSON one.
+    if check && report.pending {
+        std::process::exit(1);
+    }
+    Ok(())
+}
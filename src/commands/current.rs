@@ -1,45 +1,134 @@
-use crate::{config::Config, error::NrsError, utils::get_local_registry_url};
+use crate::{
+    config::{Config, Drift},
+    error::NrsError,
+    output::{OutputFormat, RegistryInfo, drift_text, print_json},
+    utils::{NpmrcTarget, get_npmrc_path_for, get_registry_url},
+};
 use colored::Colorize;
+use serde::Serialize;
 
-pub fn execute(config: Config, local: bool) -> Result<(), NrsError> {
-    if local {
-        match get_local_registry_url()? {
+#[derive(Serialize)]
+struct CurrentReport {
+    /// `None` when nothing is selected or the URL is not a known registry.
+    registry: Option<RegistryInfo>,
+    url: Option<String>,
+    source: String,
+    scopes: Vec<ScopeReport>,
+    /// How `~/.npmrc` disagrees with the nrs selection, only checked for the user npmrc.
+    drift: Option<Drift>,
+}
+
+#[derive(Serialize)]
+struct ScopeReport {
+    scope: String,
+    registry: String,
+    url: Option<String>,
+}
+
+pub fn execute(config: Config, target: NpmrcTarget, format: OutputFormat) -> Result<(), NrsError> {
+    let mut scopes: Vec<_> = config.scopes.iter().collect();
+    scopes.sort();
+    let drift = if target == NpmrcTarget::User {
+        config.drift(target)?
+    } else {
+        None
+    };
+
+    if format.is_json() {
+        let (url, source) = if target != NpmrcTarget::User {
+            (
+                get_registry_url(target)?,
+                get_npmrc_path_for(target)?.display().to_string(),
+            )
+        } else {
+            (
+                config
+                    .current
+                    .as_deref()
+                    .and_then(|current| config.get_url(current))
+                    .cloned(),
+                "nrs".to_string(),
+            )
+        };
+        let registry = url
+            .as_deref()
+            .and_then(|url| config.name_for_url(url))
+            .and_then(|name| RegistryInfo::new(&config, name));
+        return print_json(&CurrentReport {
+            registry,
+            url,
+            source,
+            scopes: scopes
+                .iter()
+                .map(|(scope, name)| ScopeReport {
+                    scope: scope.to_string(),
+                    registry: name.to_string(),
+                    url: config.get_url(name).cloned(),
+                })
+                .collect(),
+            drift,
+        });
+    }
+
+    if target != NpmrcTarget::User {
+        match get_registry_url(target)? {
             Some(url) => {
-                let registry_name = config
-                    .registries
-                    .iter()
-                    .chain(config.custom_registries.iter())
-                    .find(|(_, registry_url)| *registry_url == &url)
-                    .map(|(name, _)| name.as_str())
-                    .unwrap_or("(Unknown registry)");
+                let registry_name = config.name_for_url(&url).unwrap_or("(Unknown registry)");
 
                 println!(
                     "{} {} ({}) {}",
-                    "Current local registry:".bold(),
+                    "Current registry:".bold(),
                     registry_name.green().bold(),
                     url,
-                    "(local .npmrc)".dimmed()
+                    format!("({})", target.label()).dimmed()
                 )
             }
             None => {
-                println!("{}", "No registry found in local .npmrc".yellow())
+                println!(
+                    "{}",
+                    format!("No registry found in {}", target.label()).yellow()
+                )
             }
         }
     } else if let Some(current) = &config.current {
+        match config.get_url(current) {
+            Some(url) => println!(
+                "{} {} ({}) {}",
+                "Current registry:".bold(),
+                current.green().bold(),
+                url,
+                "(global)".dimmed()
+            ),
+            None => println!(
+                "{} {} {}",
+                "Current registry:".bold(),
+                current.yellow().bold(),
+                "(unknown registry, pick another with nrs use)".yellow()
+            ),
+        }
+    } else {
+        println!("{}", "No registry selected".yellow());
+    }
+    if let Some(drift) = &drift {
+        println!(
+            "{} {} (see nrs sync)",
+            format.icon("⚠️  Drift:", "Drift:").yellow(),
+            drift_text(drift, target)
+        );
+    }
+
+    for (scope, name) in scopes {
         let url = config
-            .registries
-            .get(current)
-            .or_else(|| config.custom_registries.get(current))
-            .unwrap();
+            .get_url(name)
+            .map(String::as_str)
+            .unwrap_or("(unknown registry)");
         println!(
-            "{} {} ({}) {}",
-            "Current registry:".bold(),
-            current.green().bold(),
-            url,
-            "(global)".dimmed()
+            "{} {} -> {} ({})",
+            "Scope:".bold(),
+            scope,
+            name.green(),
+            url
         );
-    } else {
-        println!("{}", "No registry selected".yellow());
     }
     Ok(())
 }
@@ -1,24 +1,84 @@
-use crate::{config::Config, error::NrsError, utils::get_npmrc_path};
+use crate::{
+    config::{Config, Drift},
+    error::NrsError,
+    output::{OutputFormat, drift_text, print_json},
+    registry::Origin,
+    utils::{NpmrcTarget, get_npmrc_path},
+};
 use colored::Colorize;
+use serde::Serialize;
 
-pub fn execute(config: Config) -> Result<(), NrsError> {
+#[derive(Serialize)]
+struct DoctorReport {
+    npmrc: FileStatus,
+    registries: RegistryCounts,
+    current: Option<String>,
+    drift: Option<Drift>,
+}
+
+#[derive(Serialize)]
+struct FileStatus {
+    path: String,
+    exists: bool,
+}
+
+#[derive(Serialize)]
+struct RegistryCounts {
+    total: usize,
+    default: usize,
+    custom: usize,
+    project: usize,
+}
+
+pub fn execute(config: Config, format: OutputFormat) -> Result<(), NrsError> {
     let npmrc_path = get_npmrc_path()?;
+    let total_registries = config.registries.len();
+    let builtin = config
+        .registries
+        .iter()
+        .filter(|registry| registry.origin == Origin::Builtin)
+        .count();
+    let project = config
+        .registries
+        .iter()
+        .filter(|registry| registry.origin == Origin::Project)
+        .count();
+    let custom = total_registries - builtin - project;
+    let drift = config.drift(NpmrcTarget::User)?;
+
+    if format.is_json() {
+        return print_json(&DoctorReport {
+            npmrc: FileStatus {
+                path: npmrc_path.display().to_string(),
+                exists: npmrc_path.exists(),
+            },
+            registries: RegistryCounts {
+                total: total_registries,
+                default: builtin,
+                custom,
+                project,
+            },
+            current: config.current.clone(),
+            drift,
+        });
+    }
+
     if !npmrc_path.exists() {
         println!("{} {}", "Missing .npmrc:".red(), npmrc_path.display());
     } else {
         println!("{} {}", ".npmrc found:".green(), npmrc_path.display());
     }
 
-    let total_registries = config.registries.len() + config.custom_registries.len();
     if total_registries == 0 {
         println!("{}", "No registries configured".red());
     } else {
         println!(
-            "{} {} ({} default, {} custom)",
+            "{} {} ({} default, {} custom, {} from the project)",
             "Total registries:".green(),
             total_registries,
-            config.registries.len(),
-            config.custom_registries.len()
+            builtin,
+            custom,
+            project
         );
     }
 
@@ -27,5 +87,14 @@ pub fn execute(config: Config) -> Result<(), NrsError> {
     } else {
         println!("{}", "No current registry set.".yellow());
     }
+
+    match &drift {
+        Some(drift) => println!(
+            "{} {} (run nrs sync --adopt or --apply)",
+            "Drift:".red(),
+            drift_text(drift, NpmrcTarget::User)
+        ),
+        None => println!("{}", "~/.npmrc matches nrs".green()),
+    }
     Ok(())
 }
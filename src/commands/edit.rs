@@ -1,7 +1,159 @@
-use crate::{config::Config, error::NrsError};
+use std::str::FromStr;
 
-pub fn execute(mut config: Config, name: &str, new_url: &str) -> Result<(), NrsError> {
-    config.edit_registry(name, new_url)?;
-    println!("Edited registry: {} ({})", name, new_url);
+use clap::{Args, ValueHint};
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::{
+    config::Config,
+    error::NrsError,
+    output::{OutputFormat, print_json},
+    probe::ProbeStrategy,
+    registry::Registry,
+    utils::parse_duration,
+};
+
+#[derive(Args)]
+pub struct EditArgs {
+    #[arg(value_hint = ValueHint::Other)]
+    name: String,
+    #[arg(
+        value_hint = ValueHint::Url,
+        required_unless_present_any = [
+            "probe_timeout", "probe", "keep", "no_keep", "description",
+            "home", "tag", "untag", "note", "disable", "enable",
+        ]
+    )]
+    new_url: Option<String>,
+    /// Probe timeout for this registry, e.g. 30s, or "default" to clear it
+    #[arg(long)]
+    probe_timeout: Option<String>,
+    /// How to check this registry: ping, head or package:<name>
+    #[arg(long, value_parser = ProbeStrategy::from_str)]
+    probe: Option<ProbeStrategy>,
+    /// Never let prune remove this registry
+    #[arg(long)]
+    keep: bool,
+    /// Undo --keep
+    #[arg(long, conflicts_with = "keep")]
+    no_keep: bool,
+    /// Short description shown by ls --long ("" clears it)
+    #[arg(long)]
+    description: Option<String>,
+    /// Web page of the registry, e.g. its UI or docs ("" clears it)
+    #[arg(long, value_hint = ValueHint::Url)]
+    home: Option<String>,
+    /// Add tags, e.g. --tag china,mirror
+    #[arg(long, value_delimiter = ',')]
+    tag: Vec<String>,
+    /// Remove tags
+    #[arg(long, value_delimiter = ',')]
+    untag: Vec<String>,
+    /// Free-form notes ("" clears them)
+    #[arg(long)]
+    note: Option<String>,
+    /// Hide the registry from use, test and use --fastest
+    #[arg(long)]
+    disable: bool,
+    /// Undo --disable
+    #[arg(long, conflicts_with = "disable")]
+    enable: bool,
+}
+
+#[derive(Serialize)]
+struct EditReport<'a> {
+    action: &'static str,
+    name: &'a str,
+    registry: Option<&'a Registry>,
+}
+
+pub fn execute(mut config: Config, args: EditArgs, format: OutputFormat) -> Result<(), NrsError> {
+    let name = args.name.as_str();
+    if let Some(new_url) = &args.new_url {
+        config.edit_registry(name, new_url)?;
+    }
+    let timeout = match args.probe_timeout.as_deref() {
+        None => None,
+        Some("default") => Some(None),
+        Some(value) => Some(Some(
+            parse_duration(value).map_err(NrsError::InvalidDuration)?,
+        )),
+    };
+    let text = |value: &Option<String>| {
+        value
+            .as_ref()
+            .map(|value| Some(value.trim().to_string()).filter(|value| !value.is_empty()))
+    };
+    let (description, home, note) = (text(&args.description), text(&args.home), text(&args.note));
+    config.update_registry(name, |registry| {
+        if let Some(timeout) = timeout {
+            registry.timeout_ms = timeout.map(|timeout| timeout.as_millis() as u64);
+        }
+        if let Some(probe) = &args.probe {
+            registry.probe = probe.clone();
+        }
+        if args.keep || args.no_keep {
+            registry.keep = args.keep;
+        }
+        if args.disable || args.enable {
+            registry.disabled = args.disable;
+        }
+        if let Some(description) = description {
+            registry.description = description;
+        }
+        if let Some(home) = home {
+            registry.home = home;
+        }
+        if let Some(note) = note {
+            registry.notes = note;
+        }
+        for tag in &args.tag {
+            let tag = tag.trim();
+            if !tag.is_empty() && !registry.has_tag(tag) {
+                registry.tags.push(tag.to_string());
+            }
+        }
+        registry.tags.retain(|tag| !args.untag.contains(tag));
+    })?;
+
+    let registry = config.get(name);
+    if format.is_json() {
+        return print_json(&EditReport {
+            action: "edit",
+            name,
+            registry,
+        });
+    }
+    let Some(registry) = registry else {
+        return Ok(());
+    };
+    if let Some(new_url) = &args.new_url {
+        println!("Edited registry: {} ({})", name, new_url);
+    }
+    if args.probe_timeout.is_some() {
+        match registry.timeout_ms {
+            Some(ms) => println!("Probe timeout for {}: {}ms", name, ms),
+            None => println!("Probe timeout for {}: default", name),
+        }
+    }
+    if args.probe.is_some() {
+        println!("Probe strategy for {}: {}", name, registry.probe);
+    }
+    if args.keep {
+        println!("{} will never be removed by prune", name);
+    } else if args.no_keep {
+        println!("{} can be removed by prune again", name);
+    }
+    if args.disable {
+        println!("{} is disabled", name.yellow());
+    } else if args.enable {
+        println!("{} is enabled", name.green());
+    }
+    if args.description.is_some() || args.home.is_some() || args.note.is_some() {
+        println!("Updated details for {}", name);
+    }
+    if !args.tag.is_empty() || !args.untag.is_empty() {
+        println!("Tags for {}: {}", name, registry.tags.join(", "));
+    }
     Ok(())
 }
@@ -0,0 +1,79 @@
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::{
+    config::Config,
+    effective::{Source, resolve_registry},
+    error::NrsError,
+    output::{OutputFormat, print_json},
+};
+
+#[derive(Serialize)]
+struct EffectiveReport<'a> {
+    package: Option<&'a str>,
+    registry: Option<&'a str>,
+    url: &'a str,
+    key: &'a str,
+    source: SourceReport,
+}
+
+#[derive(Serialize)]
+struct SourceReport {
+    /// One of `env`, `file` or `default`.
+    kind: &'static str,
+    /// The environment variable name or the file path.
+    detail: Option<String>,
+}
+
+pub fn execute(
+    config: Config,
+    package: Option<&str>,
+    format: OutputFormat,
+) -> Result<(), NrsError> {
+    let resolved = resolve_registry(package)?;
+
+    if format.is_json() {
+        let (kind, detail) = match &resolved.source {
+            Source::Env(var) => ("env", Some(var.clone())),
+            Source::File(_, path) => ("file", Some(path.display().to_string())),
+            Source::Default => ("default", None),
+        };
+        return print_json(&EffectiveReport {
+            package,
+            registry: config.name_for_url(&resolved.url),
+            url: &resolved.url,
+            key: &resolved.key,
+            source: SourceReport { kind, detail },
+        });
+    }
+
+    let name = config
+        .name_for_url(&resolved.url)
+        .unwrap_or("(Unknown registry)");
+
+    let heading = match package {
+        Some(package) => format!("Effective registry for {}:", package),
+        None => "Effective registry:".to_string(),
+    };
+    println!(
+        "{} {} ({})",
+        heading.bold(),
+        name.green().bold(),
+        resolved.url
+    );
+    println!("{} {}", "Key:".bold(), resolved.key);
+    println!("{} {}", "Source:".bold(), resolved.source);
+
+    if resolved.key == "registry"
+        && let Some(current) = &config.current
+        && current != name
+    {
+        println!(
+            "{} nrs current is {}, but npm will use {}",
+            "Warning:".yellow(),
+            current,
+            name
+        );
+    }
+    Ok(())
+}
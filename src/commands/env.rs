@@ -0,0 +1,82 @@
+use std::env;
+
+use serde::Serialize;
+
+use crate::{
+    config::{AuthToken, Config},
+    env::{EnvFormat, EnvValue, registry_vars},
+    error::NrsError,
+    output::{OutputFormat, print_json},
+};
+
+#[derive(Serialize)]
+struct EnvVar {
+    name: String,
+    value: String,
+}
+
+#[derive(Serialize)]
+struct EnvReport {
+    registry: String,
+    url: String,
+    vars: Vec<EnvVar>,
+}
+
+/// Prints the variables `nrs exec` would set for registry `name`, for a shell
+/// to evaluate or a CI system to load. Variables the format cannot carry are
+/// reported on stderr. In JSON, literal tokens are masked unless
+/// `show_secrets` is set; `$VAR` references are printed as `${VAR}`.
+pub fn execute(
+    config: Config,
+    name: &str,
+    env_format: EnvFormat,
+    show_secrets: bool,
+    format: OutputFormat,
+) -> Result<(), NrsError> {
+    let vars = registry_vars(&config, name)?;
+    if format.is_json() {
+        return print_json(&EnvReport {
+            registry: name.to_string(),
+            url: config.get_url(name).cloned().unwrap_or_default(),
+            vars: vars
+                .into_iter()
+                .map(|(name, value)| EnvVar {
+                    name,
+                    value: match value {
+                        EnvValue::Url(url) => url,
+                        EnvValue::Token(AuthToken::Token(token)) if show_secrets => token,
+                        EnvValue::Token(token) => token.masked(),
+                    },
+                })
+                .collect(),
+        });
+    }
+    for (var, value) in &vars {
+        match env_format.line(var, value) {
+            Some(line) => println!("{}", line),
+            None => eprintln!(
+                "nrs: {} skipped: {}",
+                var,
+                skip_reason(var, value, env_format)
+            ),
+        }
+    }
+    Ok(())
+}
+
+fn skip_reason(var: &str, value: &EnvValue, env_format: EnvFormat) -> String {
+    match value {
+        EnvValue::Token(_) if env_format == EnvFormat::Gitlab => {
+            "tokens are not written to report artifacts".to_string()
+        }
+        EnvValue::Token(AuthToken::Env(token_var))
+            if env_format == EnvFormat::Github && env::var(token_var).is_err() =>
+        {
+            format!("{} is not set", token_var)
+        }
+        EnvValue::Token(_) if var.ends_with(":_authToken") => {
+            "npm can't read tokens from shell variables; run npm through nrs exec".to_string()
+        }
+        _ => "this format can't set it".to_string(),
+    }
+}
@@ -0,0 +1,49 @@
+use std::{env, process};
+
+use crate::{config::Config, env::registry_vars, error::NrsError};
+
+/// Runs `command` with registry `name` selected through environment variables,
+/// leaving every `.npmrc` untouched, and exits with the command's status.
+pub fn execute(config: Config, name: &str, command: &[String]) -> Result<(), NrsError> {
+    // Tokens kept as `$VAR` references are skipped when the variable is unset.
+    let vars: Vec<(String, String)> = registry_vars(&config, name)?
+        .into_iter()
+        .filter_map(|(var, value)| Some((var, value.resolve()?)))
+        .collect();
+    let (program, args) = command
+        .split_first()
+        .expect("clap requires a command to run");
+
+    let mut child = process::Command::new(program);
+    child.args(args);
+    // npm reads config variables case-insensitively, so an inherited
+    // `npm_config_registry` would compete with the one set here.
+    for (inherited, _) in env::vars_os() {
+        if let Some(inherited) = inherited.to_str()
+            && vars
+                .iter()
+                .any(|(var, _)| var.eq_ignore_ascii_case(inherited))
+        {
+            child.env_remove(inherited);
+        }
+    }
+    child.envs(vars);
+
+    let status = child
+        .status()
+        .map_err(|err| NrsError::CommandFailed(program.clone(), err))?;
+    process::exit(exit_code(status));
+}
+
+/// The child's exit code; a child killed by a signal exits like a shell
+/// would report it, with 128 + the signal number.
+fn exit_code(status: process::ExitStatus) -> i32 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return 128 + signal;
+        }
+    }
+    status.code().unwrap_or(1)
+}
@@ -0,0 +1,32 @@
+use crate::{
+    config::Config,
+    error::NrsError,
+    output::OutputFormat,
+    share::{EXPORT_VERSION, FileFormat, RegistrySet, SharedRegistry},
+};
+
+/// Prints the registries, with their details and scopes, as a file that
+/// `nrs import` reads back, as TOML with `--format toml` and JSON otherwise.
+/// Auth tokens are never included.
+pub fn execute(config: Config, names: &[String], format: OutputFormat) -> Result<(), NrsError> {
+    let file_format = match format {
+        OutputFormat::Toml => FileFormat::Toml,
+        _ => FileFormat::Json,
+    };
+    for name in names {
+        if config.get(name).is_none() {
+            return Err(NrsError::RegistryNotFound(name.clone()));
+        }
+    }
+    let set = RegistrySet {
+        version: EXPORT_VERSION,
+        registries: config
+            .registries
+            .iter()
+            .filter(|registry| names.is_empty() || names.contains(&registry.name))
+            .map(|registry| SharedRegistry::from_registry(&config, registry))
+            .collect(),
+    };
+    print!("{}", set.to_string(file_format)?);
+    Ok(())
+}
@@ -0,0 +1,126 @@
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    config::Config,
+    env::{NPM_REGISTRY, Shell},
+    error::NrsError,
+    pin::Pin,
+};
+
+/// Path of the pin file whose registry the hook exported.
+const HOOK_PIN: &str = "NRS_HOOK_PIN";
+/// `NPM_CONFIG_REGISTRY` as it was before entering the project; unset when it
+/// was unset.
+const HOOK_PREV_REGISTRY: &str = "NRS_HOOK_PREV_REGISTRY";
+
+/// Prints the snippet that wires `nrs hook-env` into the shell's directory
+/// changes. Meant for `eval "$(nrs hook bash)"` in the shell's rc file.
+pub fn execute(shell: Shell) -> Result<(), NrsError> {
+    let exe = env::current_exe().unwrap_or_else(|_| PathBuf::from("nrs"));
+    let exe = shell.quote(&exe.to_string_lossy());
+    let snippet = match shell {
+        Shell::Bash => format!(
+            r#"_nrs_hook() {{
+  local status=$?
+  if [[ "${{_NRS_HOOK_PWD-}}" != "$PWD" ]]; then
+    _NRS_HOOK_PWD=$PWD
+    eval "$({exe} hook-env bash)"
+  fi
+  return $status
+}}
+if [[ ";${{PROMPT_COMMAND[*]:-}};" != *";_nrs_hook;"* ]]; then
+  PROMPT_COMMAND="_nrs_hook${{PROMPT_COMMAND:+;$PROMPT_COMMAND}}"
+fi"#
+        ),
+        Shell::Zsh => format!(
+            r#"_nrs_hook() {{
+  eval "$({exe} hook-env zsh)"
+}}
+typeset -ag chpwd_functions
+if (( ! ${{chpwd_functions[(I)_nrs_hook]}} )); then
+  chpwd_functions=(_nrs_hook $chpwd_functions)
+fi
+_nrs_hook"#
+        ),
+        Shell::Fish => format!(
+            r#"function __nrs_hook --on-variable PWD
+    {exe} hook-env fish | source
+end
+__nrs_hook"#
+        ),
+    };
+    println!("{}", snippet);
+    Ok(())
+}
+
+/// Prints the statements that bring `NPM_CONFIG_REGISTRY` in line with the
+/// pin file above the working directory, or restore it after leaving the
+/// project. Runs on every directory change, so it never probes, writes or
+/// takes the config lock; problems are reported on stderr and leave the
+/// environment as it is.
+pub fn env(shell: Shell) -> Result<(), NrsError> {
+    let active = env::var_os(HOOK_PIN).map(PathBuf::from);
+    let current = env::var(NPM_REGISTRY).ok();
+    let pinned = env::current_dir()
+        .ok()
+        .and_then(|cwd| Pin::find(&cwd))
+        .and_then(|path| match pinned_url(&path) {
+            Ok(url) => url.map(|url| (path, url)),
+            Err(err) => {
+                eprintln!("nrs: {}", err);
+                None
+            }
+        });
+
+    let mut statements = Vec::new();
+    match (active, pinned) {
+        (None, None) => {}
+        (None, Some((path, url))) => {
+            if let Some(previous) = &current {
+                statements.push(shell.export(HOOK_PREV_REGISTRY, previous));
+            }
+            statements.push(shell.export(NPM_REGISTRY, &url));
+            statements.push(shell.export(HOOK_PIN, &path.to_string_lossy()));
+        }
+        (Some(active), Some((path, url))) => {
+            if current.as_deref() != Some(url.as_str()) {
+                statements.push(shell.export(NPM_REGISTRY, &url));
+            }
+            if active != path {
+                statements.push(shell.export(HOOK_PIN, &path.to_string_lossy()));
+            }
+        }
+        (Some(_), None) => {
+            match env::var(HOOK_PREV_REGISTRY) {
+                Ok(previous) => statements.push(shell.export(NPM_REGISTRY, &previous)),
+                Err(_) => statements.push(shell.unset(NPM_REGISTRY)),
+            }
+            statements.push(shell.unset(HOOK_PREV_REGISTRY));
+            statements.push(shell.unset(HOOK_PIN));
+        }
+    }
+    if !statements.is_empty() {
+        println!("{}", statements.join("\n"));
+    }
+    Ok(())
+}
+
+/// URL of the registry the pin selects, resolved against `~/.nrsrc`, the
+/// presets and the pin's own definitions. `None` when it only maps scopes.
+///
+/// Scoped registries are left to the project `.npmrc` (`nrs apply`): npm's
+/// `npm_config_@scope:registry` variables are not valid shell names.
+fn pinned_url(path: &Path) -> Result<Option<String>, NrsError> {
+    let pin = Pin::load(path)?;
+    let Some(name) = &pin.registry else {
+        return Ok(None);
+    };
+    let registry = Config::lookup(name, &pin)?;
+    if registry.disabled {
+        return Err(NrsError::RegistryDisabled(name.clone()));
+    }
+    Ok(Some(registry.url.clone()))
+}
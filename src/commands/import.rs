@@ -0,0 +1,452 @@
+use std::{fs, path::PathBuf};
+
+use clap::ValueEnum;
+use colored::Colorize;
+use dirs::home_dir;
+use serde::Serialize;
+
+use crate::{
+    config::{AddOutcome, Config, Drift, normalize_scope},
+    error::NrsError,
+    npmrc::Npmrc,
+    output::{OutputFormat, print_json},
+    registry::Origin,
+    share::{FileFormat, RegistrySet, SharedRegistry},
+    utils::NpmrcTarget,
+};
+
+/// How a registry set file is combined with the existing registries.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ImportStrategy {
+    /// Add new registries and update existing ones from the file
+    Merge,
+    /// Like merge, but also move custom registries missing from the file to the trash
+    Replace,
+    /// Only add registries whose name is not taken yet
+    SkipExisting,
+}
+
+/// Tools whose registry lists can be imported.
+#[derive(Clone, Copy)]
+enum ImportSource {
+    Nrm,
+    Yrm,
+}
+
+impl ImportSource {
+    fn parse(source: &str) -> Option<Self> {
+        match source {
+            "nrm" => Some(ImportSource::Nrm),
+            "yrm" => Some(ImportSource::Yrm),
+            _ => None,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            ImportSource::Nrm => "nrm",
+            ImportSource::Yrm => "yrm",
+        }
+    }
+
+    fn default_path(self) -> Result<PathBuf, NrsError> {
+        let home = home_dir().ok_or(NrsError::HomeDirNotFound)?;
+        Ok(home.join(format!(".{}rc", self.label())))
+    }
+}
+
+#[derive(Serialize)]
+struct Imported {
+    name: String,
+    url: String,
+    home: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Conflict {
+    name: String,
+    url: Option<String>,
+    /// `same`, `exists`, `url-exists`, `name-taken`, `invalid-url`,
+    /// `invalid-scope` or `no-registry`.
+    reason: &'static str,
+    /// The nrs registry holding the URL (`same`, `url-exists`) or the name
+    /// (`exists`, `name-taken`).
+    existing: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ImportReport {
+    action: &'static str,
+    source: &'static str,
+    path: String,
+    imported: Vec<Imported>,
+    conflicts: Vec<Conflict>,
+    /// Set when the registry selected with nrm/yrm became the nrs current one.
+    current: Option<String>,
+}
+
+#[derive(Serialize)]
+struct SetImportReport {
+    action: &'static str,
+    source: &'static str,
+    path: String,
+    strategy: ImportStrategy,
+    added: Vec<String>,
+    updated: Vec<String>,
+    removed: Vec<String>,
+    /// Scopes mapped to an added or updated registry, as `@scope` names.
+    scopes: Vec<String>,
+    conflicts: Vec<Conflict>,
+}
+
+/// Imports from `nrm`, `yrm`, or a registry set file written by `nrs export`.
+pub fn execute(
+    config: Config,
+    source: &str,
+    file: Option<PathBuf>,
+    strategy: ImportStrategy,
+    format: OutputFormat,
+) -> Result<(), NrsError> {
+    match ImportSource::parse(source) {
+        Some(tool) => import_tool(config, tool, file, format),
+        None => import_set(config, PathBuf::from(source), strategy, format),
+    }
+}
+
+/// Adds a registry through [`Config::add_registry`], turning a clash into a
+/// [`Conflict`]. Returns whether it was added.
+fn add(
+    config: &mut Config,
+    name: &str,
+    url: &str,
+    conflicts: &mut Vec<Conflict>,
+) -> Result<bool, NrsError> {
+    let conflict = |reason, existing: Option<String>| Conflict {
+        name: name.to_string(),
+        url: Some(url.to_string()),
+        reason,
+        existing,
+    };
+    if let Some(existing) = config.name_for_url(url) {
+        let reason = if existing == name {
+            "same"
+        } else {
+            "url-exists"
+        };
+        conflicts.push(conflict(reason, Some(existing.to_string())));
+        return Ok(false);
+    }
+    match config.add_registry(name, url) {
+        Ok(AddOutcome::Added) => return Ok(true),
+        Ok(AddOutcome::Exists(existing)) => conflicts.push(conflict("url-exists", Some(existing))),
+        Err(NrsError::RegistryExists(_)) => {
+            conflicts.push(conflict("name-taken", Some(name.to_string())))
+        }
+        Err(NrsError::InvalidRegistryUrl(_)) => conflicts.push(conflict("invalid-url", None)),
+        Err(err) => return Err(err),
+    }
+    Ok(false)
+}
+
+/// Copies the `[name] registry=… home=…` sections of an nrm or yrm config
+/// into nrs as custom registries. Entries that clash with an existing name or
+/// URL are reported and left alone.
+fn import_tool(
+    mut config: Config,
+    source: ImportSource,
+    file: Option<PathBuf>,
+    format: OutputFormat,
+) -> Result<(), NrsError> {
+    let path = match file {
+        Some(path) => path,
+        None => source.default_path()?,
+    };
+    if !path.is_file() {
+        return Err(NrsError::ImportSourceNotFound(path));
+    }
+    let rc = Npmrc::load(&path)?;
+    let mut report = ImportReport {
+        action: "import",
+        source: source.label(),
+        path: path.display().to_string(),
+        imported: Vec::new(),
+        conflicts: Vec::new(),
+        current: None,
+    };
+
+    for name in rc.sections() {
+        let home = rc
+            .section_get(name, "home")
+            .filter(|home| !home.is_empty())
+            .map(str::to_string);
+        let Some(url) = rc
+            .section_get(name, "registry")
+            .filter(|url| !url.is_empty())
+        else {
+            report.conflicts.push(Conflict {
+                name: name.to_string(),
+                url: None,
+                reason: "no-registry",
+                existing: None,
+            });
+            continue;
+        };
+        if add(&mut config, name, url, &mut report.conflicts)? {
+            if let Some(home) = &home {
+                config.update_registry(name, |registry| registry.home = Some(home.clone()))?;
+            }
+            report.imported.push(Imported {
+                name: name.to_string(),
+                url: url.to_string(),
+                home,
+            });
+        }
+    }
+
+    // nrm and yrm select a registry by writing it to ~/.npmrc.
+    let matched = |name: &str| {
+        report.imported.iter().any(|entry| entry.name == name)
+            || report.conflicts.iter().any(|conflict| {
+                conflict.reason != "name-taken" && conflict.existing.as_deref() == Some(name)
+            })
+    };
+    if let Some(Drift::Known { name, .. }) = config.drift(NpmrcTarget::User)?
+        && matched(&name)
+    {
+        config.adopt_npmrc(NpmrcTarget::User)?;
+        report.current = Some(name);
+    }
+
+    if format.is_json() {
+        return print_json(&report);
+    }
+    println!("Importing from {}", report.path);
+    for entry in &report.imported {
+        println!("  {} {} ({})", "+".green(), entry.name, entry.url);
+    }
+    print_conflicts(&report.conflicts);
+    println!(
+        "Imported {} registr{}, {} skipped",
+        report.imported.len(),
+        if report.imported.len() == 1 {
+            "y"
+        } else {
+            "ies"
+        },
+        report.conflicts.len()
+    );
+    if let Some(current) = &report.current {
+        println!(
+            "Current registry: {} (selected with {})",
+            current,
+            source.label()
+        );
+    }
+    Ok(())
+}
+
+/// Applies a registry set written by `nrs export`. Auth tokens are never part
+/// of a set, so existing credentials are left as they are.
+fn import_set(
+    mut config: Config,
+    path: PathBuf,
+    strategy: ImportStrategy,
+    format: OutputFormat,
+) -> Result<(), NrsError> {
+    if !path.is_file() {
+        return Err(NrsError::ImportSourceNotFound(path));
+    }
+    let set = RegistrySet::parse(&fs::read_to_string(&path)?, FileFormat::for_path(&path))?;
+    let mut report = SetImportReport {
+        action: "import",
+        source: "file",
+        path: path.display().to_string(),
+        strategy,
+        added: Vec::new(),
+        updated: Vec::new(),
+        removed: Vec::new(),
+        scopes: Vec::new(),
+        conflicts: Vec::new(),
+    };
+
+    if strategy == ImportStrategy::Replace {
+        // Nothing is removed unless the whole set can be applied.
+        validate_set(&set)?;
+        let stale: Vec<String> = config
+            .registries
+            .iter()
+            .filter(|registry| registry.origin == Origin::Custom)
+            .filter(|registry| {
+                !set.registries
+                    .iter()
+                    .any(|entry| entry.name == registry.name)
+            })
+            .map(|registry| registry.name.clone())
+            .collect();
+        // Trashed rather than removed, so `nrs restore` can bring them back.
+        for name in stale {
+            config.trash_registry(&name)?;
+            report.removed.push(name);
+        }
+    }
+
+    for entry in &set.registries {
+        let name = entry.name.as_str();
+        let Some(existing) = config.get(name) else {
+            if add(&mut config, name, &entry.url, &mut report.conflicts)? {
+                config.update_registry(name, |registry| entry.apply_details(registry))?;
+                report.added.push(name.to_string());
+                map_scopes(&mut config, entry, &mut report)?;
+            }
+            continue;
+        };
+        if strategy == ImportStrategy::SkipExisting || existing.origin == Origin::Project {
+            report.conflicts.push(Conflict {
+                name: name.to_string(),
+                url: Some(entry.url.clone()),
+                reason: "exists",
+                existing: Some(name.to_string()),
+            });
+            continue;
+        }
+        let before = SharedRegistry::from_registry(&config, existing);
+        if existing.url.trim_end_matches('/') != entry.url.trim_end_matches('/') {
+            if let Some(other) = config.name_for_url(&entry.url) {
+                report.conflicts.push(Conflict {
+                    name: name.to_string(),
+                    url: Some(entry.url.clone()),
+                    reason: "url-exists",
+                    existing: Some(other.to_string()),
+                });
+                continue;
+            }
+            if let Err(NrsError::InvalidRegistryUrl(_)) = config.edit_registry(name, &entry.url) {
+                report.conflicts.push(Conflict {
+                    name: name.to_string(),
+                    url: Some(entry.url.clone()),
+                    reason: "invalid-url",
+                    existing: None,
+                });
+                continue;
+            }
+        }
+        config.update_registry(name, |registry| entry.apply_details(registry))?;
+        map_scopes(&mut config, entry, &mut report)?;
+        if let Some(registry) = config.get(name)
+            && SharedRegistry::from_registry(&config, registry) != before
+        {
+            report.updated.push(name.to_string());
+        }
+    }
+
+    if format.is_json() {
+        return print_json(&report);
+    }
+    println!(
+        "Importing from {} ({} strategy)",
+        report.path,
+        strategy_label(strategy)
+    );
+    for name in &report.added {
+        println!("  {} {}", "+".green(), name);
+    }
+    for name in &report.updated {
+        println!("  {} {}", "~".yellow(), name);
+    }
+    for name in &report.removed {
+        println!("  {} {}", "-".red(), name);
+    }
+    print_conflicts(&report.conflicts);
+    println!(
+        "{} added, {} updated, {} removed, {} skipped",
+        report.added.len(),
+        report.updated.len(),
+        report.removed.len(),
+        report.conflicts.len()
+    );
+    Ok(())
+}
+
+/// Checks every entry's URL and scopes, and that no name appears twice.
+fn validate_set(set: &RegistrySet) -> Result<(), NrsError> {
+    for (index, entry) in set.registries.iter().enumerate() {
+        let invalid = |reason: String| {
+            Err(NrsError::InvalidRegistrySet(format!(
+                "{}: {}",
+                entry.name, reason
+            )))
+        };
+        if !entry.url.starts_with("http://") && !entry.url.starts_with("https://") {
+            return invalid(format!("invalid registry URL {}", entry.url));
+        }
+        if let Some(scope) = entry
+            .scopes
+            .iter()
+            .find(|scope| normalize_scope(scope).is_err())
+        {
+            return invalid(format!("invalid scope {}", scope));
+        }
+        if set.registries[..index]
+            .iter()
+            .any(|other| other.name == entry.name)
+        {
+            return invalid("listed more than once".to_string());
+        }
+    }
+    Ok(())
+}
+
+/// Maps the entry's scopes to it, like `nrs scope set`.
+fn map_scopes(
+    config: &mut Config,
+    entry: &SharedRegistry,
+    report: &mut SetImportReport,
+) -> Result<(), NrsError> {
+    for scope in &entry.scopes {
+        if config.scopes_for(&entry.name).contains(&scope.as_str()) {
+            continue;
+        }
+        match config.set_scope(scope, &entry.name, NpmrcTarget::User) {
+            Ok(scope) => report.scopes.push(scope),
+            Err(NrsError::InvalidScope(_)) => report.conflicts.push(Conflict {
+                name: scope.clone(),
+                url: None,
+                reason: "invalid-scope",
+                existing: None,
+            }),
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(())
+}
+
+fn strategy_label(strategy: ImportStrategy) -> &'static str {
+    match strategy {
+        ImportStrategy::Merge => "merge",
+        ImportStrategy::Replace => "replace",
+        ImportStrategy::SkipExisting => "skip-existing",
+    }
+}
+
+fn print_conflicts(conflicts: &[Conflict]) {
+    for conflict in conflicts {
+        let url = conflict.url.as_deref().unwrap_or_default();
+        let existing = conflict.existing.as_deref().unwrap_or_default();
+        let detail = match conflict.reason {
+            "same" => format!("already in nrs ({})", url),
+            "exists" => "already exists, left unchanged".to_string(),
+            "url-exists" => format!("{} is already registered as {}", url, existing),
+            "name-taken" => format!("name already used by another registry, {} not added", url),
+            "invalid-url" => format!("invalid registry URL {}", url),
+            "invalid-scope" => "invalid scope".to_string(),
+            _ => "no registry URL".to_string(),
+        };
+        let marker = if conflict.reason == "same" {
+            "=".normal()
+        } else {
+            "!".yellow()
+        };
+        println!("  {} {}: {}", marker, conflict.name, detail);
+    }
+}
@@ -0,0 +1,107 @@
+use std::collections::BTreeMap;
+
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::{
+    config::Config,
+    error::NrsError,
+    output::{OutputFormat, print_json},
+    pin::{PIN_FILES, Pin},
+    registry::Origin,
+    share::SharedRegistry,
+    utils::get_project_root,
+};
+
+#[derive(Serialize)]
+struct InitReport<'a> {
+    action: &'static str,
+    path: String,
+    registry: Option<&'a str>,
+    scopes: &'a BTreeMap<String, String>,
+    defined: Vec<&'a str>,
+}
+
+/// Writes a pin file at the project root from the current selection and
+/// scope mappings, or from `registry` when given. With `define`, the pinned
+/// registries that are not presets are defined in the file too.
+pub fn execute(
+    config: Config,
+    registry: Option<String>,
+    file: &str,
+    define: bool,
+    force: bool,
+    format: OutputFormat,
+) -> Result<(), NrsError> {
+    let root = get_project_root()?;
+    if !force
+        && let Some(existing) = PIN_FILES
+            .iter()
+            .map(|name| root.join(name))
+            .find(|path| path.is_file())
+    {
+        return Err(NrsError::PinExists(existing));
+    }
+
+    let registry = registry.or_else(|| config.current.clone());
+    if let Some(name) = &registry
+        && config.get(name).is_none()
+    {
+        return Err(NrsError::RegistryNotFound(name.clone()));
+    }
+    let mut pin = Pin {
+        registry,
+        scopes: config
+            .scopes
+            .iter()
+            .map(|(scope, name)| (scope.clone(), name.clone()))
+            .collect(),
+        registries: Vec::new(),
+    };
+    if define {
+        pin.registries = config
+            .registries
+            .iter()
+            .filter(|registry| registry.origin != Origin::Builtin)
+            .filter(|registry| {
+                pin.registry.as_ref() == Some(&registry.name)
+                    || pin.scopes.values().any(|name| *name == registry.name)
+            })
+            .map(|registry| SharedRegistry {
+                scopes: Vec::new(),
+                ..SharedRegistry::from_registry(&config, registry)
+            })
+            .collect();
+    }
+    if pin.registry.is_none() && pin.scopes.is_empty() {
+        return Err(NrsError::NothingToPin);
+    }
+    let path = root.join(file);
+    pin.save(&path)?;
+
+    if format.is_json() {
+        return print_json(&InitReport {
+            action: "init",
+            path: path.display().to_string(),
+            registry: pin.registry.as_deref(),
+            scopes: &pin.scopes,
+            defined: pin
+                .registries
+                .iter()
+                .map(|entry| entry.name.as_str())
+                .collect(),
+        });
+    }
+    println!("{} {}", "Created".green(), path.display());
+    if let Some(registry) = &pin.registry {
+        println!("  registry: {}", registry);
+    }
+    for (scope, name) in &pin.scopes {
+        println!("  {}: {}", scope, name);
+    }
+    for entry in &pin.registries {
+        println!("  defines {} ({})", entry.name, entry.url);
+    }
+    println!("Commit it, then run nrs apply (or nrs use) in the project");
+    Ok(())
+}
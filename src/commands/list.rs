@@ -1,53 +1,129 @@
 use clap::ValueEnum;
 use colored::*;
 
-use crate::{config::Config, error::NrsError};
+use crate::{
+    config::Config,
+    error::NrsError,
+    history::{History, format_age},
+    output::{OutputFormat, RegistryInfo, health_label, print_json},
+    registry::{Origin, Registry},
+};
 
 #[derive(Clone, Copy, ValueEnum)]
 pub enum SortOrder {
     Name,
     Url,
+    /// The order registries were added in
     Default,
+    /// Last known latency; registries that were down or never probed go last
+    Latency,
 }
 
-pub fn execute(config: Config, sort: SortOrder) -> Result<(), NrsError> {
-    let mut registries: Vec<(String, String)> = config
-        .registries
-        .iter()
-        .chain(config.custom_registries.iter())
-        .map(|(k, v)| (k.clone(), v.clone()))
-        .collect();
+pub fn execute(
+    config: Config,
+    sort: SortOrder,
+    long: bool,
+    format: OutputFormat,
+) -> Result<(), NrsError> {
+    let history = History::load()?;
+    let mut registries: Vec<&Registry> = config.registries.iter().collect();
     match sort {
-        SortOrder::Name => registries.sort_by(|a, b| a.0.cmp(&b.0)),
-        SortOrder::Url => registries.sort_by(|a, b| a.1.cmp(&b.1)),
-        SortOrder::Default => {
-            registries.sort_by(|a, b| {
-                let a_idx = config
-                    .registry_order
-                    .iter()
-                    .position(|n| n == &a.0)
-                    .unwrap_or(usize::MAX);
-                let b_idx = config
-                    .registry_order
-                    .iter()
-                    .position(|n| n == &b.0)
-                    .unwrap_or(usize::MAX);
-                a_idx.cmp(&b_idx)
-            });
+        SortOrder::Name => registries.sort_by(|a, b| a.name.cmp(&b.name)),
+        SortOrder::Url => registries.sort_by(|a, b| a.url.cmp(&b.url)),
+        SortOrder::Default => {}
+        SortOrder::Latency => {
+            registries.sort_by_key(|registry| match history.last(&registry.name) {
+                Some(record) if !record.health.is_down() => (false, record.latency_ms),
+                _ => (true, u128::MAX),
+            })
         }
     }
-    for (name, url) in registries {
-        let marker = if config.current.as_deref() == Some(&name) {
-            format!("{}", "🟢".green())
+    if format.is_json() {
+        let infos: Vec<_> = registries
+            .iter()
+            .filter_map(|registry| {
+                let mut info = RegistryInfo::new(&config, &registry.name)?;
+                info.last_probe = history.last(&registry.name).cloned();
+                Some(info)
+            })
+            .collect();
+        return print_json(&infos);
+    }
+    for registry in registries {
+        let name = &registry.name;
+        let marker = if config.current.as_deref() == Some(name) {
+            format!("{}", format.icon("🟢", "*").green())
         } else {
             "".to_string()
         };
-        let name_str = if config.current.as_deref() == Some(&name) {
+        let name_str = if config.current.as_deref() == Some(name) {
             format!("{}", name.bold().green())
+        } else if registry.disabled {
+            name.dimmed().to_string()
         } else {
             name.normal().white().to_string()
         };
-        println!("{:2} {:15} {}", marker, name_str, url)
+        let scopes = config.scopes_for(name);
+        let scopes = if scopes.is_empty() {
+            String::new()
+        } else {
+            format!(" {}", format!("[{}]", scopes.join(", ")).dimmed())
+        };
+        let disabled = if registry.disabled {
+            format!(" {}", "(disabled)".dimmed())
+        } else {
+            String::new()
+        };
+        let disabled = if registry.origin == Origin::Project {
+            format!("{} {}", disabled, "(project)".cyan())
+        } else {
+            disabled
+        };
+        let last_probe = history
+            .last(name)
+            .map(|record| {
+                format!(
+                    " - {} {}",
+                    health_label(record.health, format),
+                    format!("{}ms, {} ago", record.latency_ms, format_age(record.age())).dimmed()
+                )
+            })
+            .unwrap_or_default();
+        println!(
+            "{:2} {:15} {}{}{}{}",
+            marker, name_str, registry.url, scopes, disabled, last_probe
+        );
+        if long {
+            print_details(registry);
+        }
     }
     Ok(())
 }
+
+/// The `--long` lines under a registry; only fields that are set are shown.
+fn print_details(registry: &Registry) {
+    let mut details = vec![("origin", registry.origin.label().to_string())];
+    if let Some(description) = &registry.description {
+        details.push(("description", description.clone()));
+    }
+    if let Some(home) = &registry.home {
+        details.push(("home", home.clone()));
+    }
+    if !registry.tags.is_empty() {
+        details.push(("tags", registry.tags.join(", ")));
+    }
+    let mut probe = registry.probe.to_string();
+    if let Some(ms) = registry.timeout_ms {
+        probe.push_str(&format!(", timeout {}ms", ms));
+    }
+    details.push(("probe", probe));
+    if registry.keep {
+        details.push(("keep", "never pruned".to_string()));
+    }
+    if let Some(notes) = &registry.notes {
+        details.push(("notes", notes.clone()));
+    }
+    for (label, value) in details {
+        println!("{:18} {} {}", "", format!("{}:", label).dimmed(), value);
+    }
+}
@@ -1,15 +1,42 @@
+use auth::AuthAction;
 use clap::{Subcommand, ValueHint};
+use config::ConfigAction;
+use edit::EditArgs;
+use import::ImportStrategy;
 use list::SortOrder;
+use presets::PresetsAction;
+use scope::ScopeAction;
+use std::{path::PathBuf, time::Duration};
+
+use crate::{
+    env::{EnvFormat, Shell},
+    utils::parse_duration,
+};
 
 mod add;
+mod adopt;
+mod apply;
+mod auth;
+mod config;
 mod current;
 mod doctor;
 mod edit;
+mod effective;
+mod env;
+mod exec;
+mod export;
+mod hook;
+mod import;
+mod init;
 mod list;
+mod presets;
 mod prune;
 mod remove;
 mod reset;
+mod restore;
+mod scope;
 mod show;
+mod sync;
 mod test;
 mod r#use;
 
@@ -19,20 +46,44 @@ pub enum Commands {
     Ls {
         #[arg(long, default_value = "name")]
         sort: SortOrder,
+        /// Show descriptions, tags, probe settings and notes
+        #[arg(long)]
+        long: bool,
     },
     /// Show current registry
     Current {
         #[arg(long)]
         local: bool,
+        #[arg(long, conflicts_with = "local")]
+        global_npmrc: bool,
     },
-    /// Use a specific registry
+    /// Use a specific registry, or with no name apply the project's pin file
     Use {
-        #[arg(value_hint = ValueHint::Other)]
-        name: String,
+        #[arg(value_hint = ValueHint::Other, conflicts_with = "fastest")]
+        name: Option<String>,
+        /// Probe the candidates and use the fastest healthy one
+        #[arg(long)]
+        fastest: bool,
+        /// Restrict --fastest to these registries, e.g. npm,taobao,corp
+        #[arg(long, value_delimiter = ',', requires = "fastest")]
+        among: Vec<String>,
+        /// Restrict --fastest to the registries with this tag
+        #[arg(long, requires = "fastest", conflicts_with = "among")]
+        tag: Option<String>,
+        /// Timed requests per candidate with --fastest
+        #[arg(
+            long,
+            default_value_t = 3,
+            requires = "fastest",
+            value_parser = clap::value_parser!(u16).range(1..)
+        )]
+        samples: u16,
         #[arg(long)]
         backup: bool,
         #[arg(long)]
         local: bool,
+        #[arg(long, conflicts_with = "local")]
+        global_npmrc: bool,
     },
     /// Add a new registry
     Add {
@@ -41,6 +92,11 @@ pub enum Commands {
         #[arg(value_hint = ValueHint::Url)]
         url: String,
     },
+    /// Save a registry defined by the project pin file to ~/.nrsrc
+    Adopt {
+        #[arg(value_hint = ValueHint::Other)]
+        name: String,
+    },
     /// Remove a registry
     Remove {
         #[arg(value_hint = ValueHint::Other)]
@@ -53,26 +109,34 @@ pub enum Commands {
         #[arg(long)]
         all: bool,
     },
-    /// Remove registries that are not reachable
+    /// Move custom registries that keep failing to the trash
     Prune {
         #[arg(long)]
         local: bool,
         #[arg(long)]
         dry_run: bool,
+        /// Consecutive failed probes, this run included, before a registry is removed
+        #[arg(long, default_value_t = 3, value_parser = clap::value_parser!(u16).range(1..))]
+        min_failures: u16,
+        /// Only count failures recorded within this window, e.g. 24h, 7d
+        #[arg(long, default_value = "24h", value_parser = parse_duration)]
+        window: Duration,
     },
-    /// Check the environment and config for problems
-    Doctor,
-    /// Edit a registry
-    Edit {
+    /// Restore a pruned registry, or list the trash
+    Restore {
         #[arg(value_hint = ValueHint::Other)]
-        name: String,
-        #[arg(value_hint = ValueHint::Url)]
-        new_url: String,
+        name: Option<String>,
     },
+    /// Check the environment and config for problems
+    Doctor,
+    /// Edit a registry's URL, probe settings or details
+    Edit(EditArgs),
     /// Show current npmrc
     Show {
         #[arg(long)]
         local: bool,
+        #[arg(long, conflicts_with = "local")]
+        global_npmrc: bool,
     },
     /// Test registry availability
     Test {
@@ -80,17 +144,147 @@ pub enum Commands {
         name: String,
         #[arg(long)]
         local: bool,
+        /// Timed requests per registry after a warm-up, for latency statistics
+        #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..))]
+        samples: u16,
+        /// Reuse results from the probe history that are newer than --max-age
+        #[arg(long, conflicts_with = "local")]
+        cached: bool,
+        /// How old a cached result may be, e.g. 10m, 1h
+        #[arg(long, default_value = "10m", value_parser = parse_duration, requires = "cached")]
+        max_age: Duration,
+    },
+    /// Manage scoped registries (@scope:registry)
+    Scope {
+        #[command(subcommand)]
+        action: ScopeAction,
+    },
+    /// Show the registry npm will actually use, and where it comes from
+    Effective {
+        /// Package name, e.g. @acme/ui, to resolve scoped registries
+        #[arg(value_hint = ValueHint::Other)]
+        package: Option<String>,
+    },
+    /// Reconcile nrs with the registry set in an npmrc
+    Sync {
+        /// Take the npmrc registry into nrs, adding it if unknown
+        #[arg(long)]
+        adopt: bool,
+        /// Write the nrs selection to the npmrc
+        #[arg(long, conflicts_with = "adopt")]
+        apply: bool,
+        #[arg(long)]
+        local: bool,
+        #[arg(long, conflicts_with = "local")]
+        global_npmrc: bool,
+    },
+    /// Inspect and upgrade the nrs config file
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Pin the current registry and scopes in a project file (.nrs)
+    Init {
+        /// Pin this registry instead of the current one
+        #[arg(long, value_hint = ValueHint::Other)]
+        registry: Option<String>,
+        #[arg(long, default_value = ".nrs", value_parser = [".nrs", ".nrsrc.json"])]
+        file: String,
+        /// Also define the pinned custom registries, for developers who lack them
+        #[arg(long)]
+        define: bool,
+        /// Overwrite an existing pin file
+        #[arg(long)]
+        force: bool,
+    },
+    /// Write the nearest pin file (.nrs or .nrsrc.json) to the project .npmrc
+    Apply {
+        #[arg(long)]
+        backup: bool,
+    },
+    /// Print registries and their scopes as a shareable file (never tokens)
+    Export {
+        /// Only these registries, e.g. corp,github
+        #[arg(long, value_delimiter = ',')]
+        names: Vec<String>,
+    },
+    /// Import registries from nrm, yrm, or a file written by nrs export
+    Import {
+        /// nrm, yrm, or the path of a .json or .toml registry set
+        #[arg(value_hint = ValueHint::FilePath)]
+        source: String,
+        /// With nrm or yrm, read this file instead of ~/.nrmrc or ~/.yrmrc
+        #[arg(long, value_hint = ValueHint::FilePath)]
+        file: Option<PathBuf>,
+        /// How a registry set is combined with your registries
+        #[arg(long, default_value = "merge")]
+        strategy: ImportStrategy,
+    },
+    /// Run a command with a registry selected through environment variables
+    Exec {
+        #[arg(value_hint = ValueHint::Other)]
+        name: String,
+        /// The command and its arguments, e.g. -- npm install
+        #[arg(
+            required = true,
+            trailing_var_arg = true,
+            allow_hyphen_values = true,
+            value_hint = ValueHint::CommandWithArguments
+        )]
+        command: Vec<String>,
+    },
+    /// Print the variables exec sets (posix, fish and gitlab give npm no token; use nrs exec)
+    Env {
+        #[arg(value_hint = ValueHint::Other)]
+        name: String,
+        #[arg(long, default_value = "posix")]
+        env_format: EnvFormat,
+        /// Print literal tokens unmasked in JSON output
+        #[arg(long)]
+        show_secrets: bool,
+    },
+    /// Print a shell snippet that follows the project pin file on every cd
+    Hook { shell: Shell },
+    /// Print the statements the shell hook evaluates (used by nrs hook)
+    #[command(hide = true)]
+    HookEnv { shell: Shell },
+    /// Compare your registries with the built-in presets
+    Presets {
+        #[command(subcommand)]
+        action: PresetsAction,
+    },
+    /// Manage registry auth tokens (//host/:_authToken)
+    Auth {
+        #[command(subcommand)]
+        action: AuthAction,
     },
 }
 
 pub use add::execute as add;
+pub use adopt::execute as adopt;
+pub use apply::execute as apply;
+pub use auth::execute as auth;
+pub use config::execute as config_cmd;
 pub use current::execute as current;
 pub use doctor::execute as doctor;
 pub use edit::execute as edit;
+pub use effective::execute as effective;
+pub use env::execute as env_cmd;
+pub use exec::execute as exec;
+pub use export::execute as export;
+pub use hook::env as hook_env;
+pub use hook::execute as hook;
+pub use import::execute as import;
+pub use init::execute as init;
 pub use list::execute as list;
+pub use presets::execute as presets;
 pub use prune::execute as prune;
 pub use remove::execute as remove;
 pub use reset::execute as reset;
+pub use restore::execute as restore;
+pub use scope::execute as scope;
 pub use show::execute as show;
+pub use sync::execute as sync;
 pub use test::execute as test;
 pub use r#use::execute as use_cmd;
+pub use r#use::{Race, fastest as use_fastest};
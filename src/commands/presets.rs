@@ -0,0 +1,311 @@
+use clap::{Subcommand, ValueHint};
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::{
+    config::Config,
+    error::NrsError,
+    output::{OutputFormat, print_json},
+    presets::{PACKS, PRESETS, Preset, in_packs},
+    registry::{Origin, Registry},
+};
+
+#[derive(Subcommand)]
+pub enum PresetsAction {
+    /// List the presets of the enabled packs and whether you changed them
+    Ls,
+    /// Show how your registries differ from the built-in presets
+    Diff,
+    /// Drop your overrides and removals so the built-ins apply again
+    Update {
+        /// Presets to update; all of them when omitted
+        #[arg(value_hint = ValueHint::Other)]
+        names: Vec<String>,
+    },
+    /// List the preset packs and which are enabled
+    Packs,
+    /// Add the presets of a pack, e.g. china or enterprise-templates
+    Enable {
+        #[arg(required = true, value_hint = ValueHint::Other)]
+        packs: Vec<String>,
+    },
+    /// Remove the presets of a pack; ones you changed or use are kept as custom registries
+    Disable {
+        #[arg(required = true, value_hint = ValueHint::Other)]
+        packs: Vec<String>,
+    },
+}
+
+#[derive(Serialize)]
+struct PresetReport<'a> {
+    name: &'a str,
+    url: &'a str,
+    description: &'a str,
+    home: &'a str,
+    pack: &'a str,
+    /// default, overridden, removed or shadowed (a custom registry took the name).
+    status: &'static str,
+    changes: Vec<Change>,
+}
+
+#[derive(Serialize)]
+struct Change {
+    field: &'static str,
+    builtin: String,
+    user: String,
+}
+
+#[derive(Serialize)]
+struct PackReport {
+    name: &'static str,
+    description: &'static str,
+    enabled: bool,
+    presets: Vec<&'static str>,
+}
+
+#[derive(Serialize)]
+struct PackChangeReport {
+    action: &'static str,
+    pack: String,
+    /// Presets added by `enable`, or dropped by `disable`.
+    presets: Vec<String>,
+    /// Presets `disable` kept as custom registries.
+    kept: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct UpdateReport {
+    action: &'static str,
+    updated: Vec<String>,
+    skipped: Vec<String>,
+}
+
+pub fn execute(
+    mut config: Config,
+    action: PresetsAction,
+    format: OutputFormat,
+) -> Result<(), NrsError> {
+    match action {
+        PresetsAction::Ls => report(&config, false, format),
+        PresetsAction::Diff => report(&config, true, format),
+        PresetsAction::Update { names } => {
+            let (updated, skipped) = config.reset_presets(&names)?;
+            if format.is_json() {
+                return print_json(&UpdateReport {
+                    action: "update",
+                    updated,
+                    skipped,
+                });
+            }
+            if updated.is_empty() {
+                println!("Presets are already up to date");
+            }
+            for name in &updated {
+                println!("{} {}", "Updated:".green(), name);
+            }
+            for name in &skipped {
+                println!(
+                    "{} {} is one of your own registries; remove it first to get the preset back",
+                    "Skipped:".yellow(),
+                    name
+                );
+            }
+            Ok(())
+        }
+        PresetsAction::Packs => packs(&config, format),
+        PresetsAction::Enable { packs } => {
+            let mut reports = Vec::new();
+            for pack in packs {
+                let presets = config.enable_pack(&pack)?;
+                reports.push(PackChangeReport {
+                    action: "enable",
+                    pack,
+                    presets,
+                    kept: Vec::new(),
+                });
+            }
+            print_pack_changes(&reports, format)
+        }
+        PresetsAction::Disable { packs } => {
+            let mut reports = Vec::new();
+            for pack in packs {
+                let (presets, kept) = config.disable_pack(&pack)?;
+                reports.push(PackChangeReport {
+                    action: "disable",
+                    pack,
+                    presets,
+                    kept,
+                });
+            }
+            print_pack_changes(&reports, format)
+        }
+    }
+}
+
+fn packs(config: &Config, format: OutputFormat) -> Result<(), NrsError> {
+    let reports: Vec<PackReport> = PACKS
+        .iter()
+        .map(|pack| PackReport {
+            name: pack.name,
+            description: pack.description,
+            enabled: config.packs.iter().any(|enabled| enabled == pack.name),
+            presets: PRESETS
+                .iter()
+                .filter(|preset| preset.pack == pack.name)
+                .map(|preset| preset.name)
+                .collect(),
+        })
+        .collect();
+    if format.is_json() {
+        return print_json(&reports);
+    }
+    for report in &reports {
+        let mark = if report.enabled {
+            format!("{}", format.icon("🟢", "*").green())
+        } else {
+            "  ".to_string()
+        };
+        println!("{} {:<22} {}", mark, report.name, report.description);
+        println!("   {:<22} {}", "", report.presets.join(", ").dimmed());
+    }
+    Ok(())
+}
+
+fn print_pack_changes(reports: &[PackChangeReport], format: OutputFormat) -> Result<(), NrsError> {
+    if format.is_json() {
+        return print_json(&reports);
+    }
+    for report in reports {
+        let verb = if report.action == "enable" {
+            "Enabled pack:".green()
+        } else {
+            "Disabled pack:".yellow()
+        };
+        println!("{} {}", verb, report.pack);
+        if !report.presets.is_empty() {
+            let what = if report.action == "enable" {
+                "Added"
+            } else {
+                "Removed"
+            };
+            println!("  {}: {}", what, report.presets.join(", "));
+        }
+        if !report.kept.is_empty() {
+            println!(
+                "  Kept as custom registries (changed or in use): {}",
+                report.kept.join(", ")
+            );
+        }
+    }
+    Ok(())
+}
+
+fn report(config: &Config, diff_only: bool, format: OutputFormat) -> Result<(), NrsError> {
+    let reports: Vec<PresetReport> = in_packs(&config.packs)
+        .map(|preset| preset_report(config, preset))
+        .filter(|report| !diff_only || report.status != "default")
+        .collect();
+    if format.is_json() {
+        return print_json(&reports);
+    }
+    if reports.is_empty() {
+        println!("Your registries match the built-in presets");
+        return Ok(());
+    }
+    for report in &reports {
+        let status = match report.status {
+            "default" => report.status.green(),
+            "overridden" => report.status.yellow(),
+            _ => report.status.red(),
+        };
+        if diff_only {
+            println!("{} ({})", report.name.bold(), status);
+        } else {
+            println!("{:<10} {:<40} {}", report.name, report.url, status);
+        }
+        if !diff_only {
+            continue;
+        }
+        match report.status {
+            "removed" => println!("  removed, built-in: {}", report.url),
+            "shadowed" => println!(
+                "  a custom registry uses this name, built-in: {}",
+                report.url
+            ),
+            _ => {}
+        }
+        for change in &report.changes {
+            println!(
+                "  {}: {} -> {}",
+                change.field,
+                change.builtin.red(),
+                change.user.green()
+            );
+        }
+    }
+    if diff_only {
+        println!("Run nrs presets update [name...] to take the built-ins again");
+    }
+    Ok(())
+}
+
+fn preset_report<'a>(config: &Config, preset: &'a Preset) -> PresetReport<'a> {
+    let mut report = PresetReport {
+        name: preset.name,
+        url: preset.url,
+        description: preset.description,
+        home: preset.home,
+        pack: preset.pack,
+        status: "default",
+        changes: Vec::new(),
+    };
+    match config.get(preset.name) {
+        None => report.status = "removed",
+        Some(registry) if registry.origin != Origin::Builtin => report.status = "shadowed",
+        Some(registry) => {
+            report.changes = changes(&preset.registry(), registry);
+            if !report.changes.is_empty() {
+                report.status = "overridden";
+            }
+        }
+    }
+    report
+}
+
+/// Fields of `user` that differ from the shipped `builtin`.
+fn changes(builtin: &Registry, user: &Registry) -> Vec<Change> {
+    let text = |value: &Option<String>| value.clone().unwrap_or_default();
+    let timeout = |value: Option<u64>| value.map(|ms| format!("{}ms", ms)).unwrap_or_default();
+    let fields = [
+        ("url", builtin.url.clone(), user.url.clone()),
+        (
+            "description",
+            text(&builtin.description),
+            text(&user.description),
+        ),
+        ("home", text(&builtin.home), text(&user.home)),
+        ("tags", builtin.tags.join(","), user.tags.join(",")),
+        (
+            "timeout",
+            timeout(builtin.timeout_ms),
+            timeout(user.timeout_ms),
+        ),
+        ("probe", builtin.probe.to_string(), user.probe.to_string()),
+        ("notes", text(&builtin.notes), text(&user.notes)),
+        ("keep", builtin.keep.to_string(), user.keep.to_string()),
+        (
+            "disabled",
+            builtin.disabled.to_string(),
+            user.disabled.to_string(),
+        ),
+    ];
+    fields
+        .into_iter()
+        .filter(|(_, builtin, user)| builtin != user)
+        .map(|(field, builtin, user)| Change {
+            field,
+            builtin,
+            user,
+        })
+        .collect()
+}
@@ -1,142 +1,253 @@
-use std::{
-    fs::{self, File},
-    io::{BufRead, BufReader},
-    time::Instant,
-};
+use std::time::Duration;
 
 use colored::Colorize;
+use serde::Serialize;
 
 use crate::{
     config::Config,
     error::NrsError,
-    utils::{get_local_registry_url, get_npmrc_path, get_npmrc_path_local},
+    history::History,
+    npmrc::Npmrc,
+    output::{OutputFormat, ProbeReport, health_label, print_json},
+    probe::{ProbeOptions, Prober},
+    registry::Origin,
+    utils::{NpmrcTarget, get_npmrc_path_for, get_registry_url, lock_config},
 };
 
-pub fn execute(mut config: Config, local: bool, dry_run: bool) -> Result<(), NrsError> {
-    if local {
-        let npmrc_path = get_npmrc_path_local(true)?;
-        let local_url = get_local_registry_url()?;
-        if let Some(url) = local_url {
-            let client = reqwest::blocking::Client::new();
-            let start = Instant::now();
-            let response = client.head(&url).send();
-            let duration = start.elapsed().as_millis();
-            let reachable = matches!(response, Ok(resp) if resp.status().is_success());
+#[derive(Serialize)]
+struct PruneReport {
+    target: &'static str,
+    dry_run: bool,
+    results: Vec<ProbeReport>,
+    /// Registries moved to the trash, or the project registry line removed
+    /// with `--local` (or that would be with `--dry-run`).
+    removed: Vec<RemovedReport>,
+    /// Down registries that were not removed, and why.
+    kept: Vec<KeptReport>,
+}
 
-            if reachable {
-                println!(
-                    "{} {} ({}ms)",
-                    "✅ Local registry is reachable:".green(),
-                    url,
-                    duration
-                );
-            } else {
-                println!(
-                    "{} {} ({}ms)",
-                    "🔴 Local registry is NOT reachable:".red(),
-                    url,
-                    duration
-                );
-                if !dry_run {
-                    if npmrc_path.exists() {
-                        let mut lines = Vec::new();
-                        let file = File::open(&npmrc_path)?;
-                        let reader = BufReader::new(file);
-                        for line in reader.lines() {
-                            let line = line?;
-                            if !line.trim().starts_with("registry=") {
-                                lines.push(line);
+#[derive(Serialize)]
+struct RemovedReport {
+    /// `None` for a `--local` URL that is not a known registry.
+    name: Option<String>,
+    url: String,
+}
+
+#[derive(Serialize)]
+struct KeptReport {
+    name: String,
+    /// `current`, `keep` or `not-enough-failures`.
+    reason: &'static str,
+    /// Consecutive failures recorded within the window, this run included.
+    failures: usize,
+}
+
+/// Probes without the config lock, which is only taken once the results are
+/// in, to record them and remove what failed.
+pub fn execute(
+    config: Config,
+    local: bool,
+    dry_run: bool,
+    min_failures: usize,
+    window: Duration,
+    options: ProbeOptions,
+    format: OutputFormat,
+) -> Result<(), NrsError> {
+    let prober = Prober::new(options)?;
+    let json = format.is_json();
+    let mut report = PruneReport {
+        target: if local { "local" } else { "custom" },
+        dry_run,
+        results: Vec::new(),
+        removed: Vec::new(),
+        kept: Vec::new(),
+    };
+
+    if local {
+        let npmrc_path = get_npmrc_path_for(NpmrcTarget::Project)?;
+        let Some(url) = get_registry_url(NpmrcTarget::Project)? else {
+            if !json {
+                println!("{}", "No registry found in local .npmrc".yellow());
+            }
+            return if json { print_json(&report) } else { Ok(()) };
+        };
+        let target = config.probe_target_for_url(&url, &options);
+        let result = prober.probe(&target);
+        let probe = ProbeReport::new(target, result);
+        if !json {
+            println!(
+                "{} {} - {} ({}ms)",
+                "Local registry:".bold(),
+                probe.url,
+                health_label(probe.health, format),
+                probe.latency_ms.unwrap_or_default()
+            );
+        }
+        let name = config.name_for_url(&url).map(str::to_string);
+        // Unknown URLs are tracked under the URL itself.
+        let key = name.clone().unwrap_or_else(|| url.clone());
+        let _lock = lock_config()?;
+        let mut history = History::load()?;
+        history.record_as(&key, &probe);
+        history.save()?;
+        if probe.health.is_down() {
+            let failures = history.consecutive_failures(&key, &probe.url, window);
+            match keep_reason(&config, name.as_deref(), failures, min_failures) {
+                Some(reason) => report.kept.push(KeptReport {
+                    name: key,
+                    reason,
+                    failures,
+                }),
+                None => {
+                    report.removed.push(RemovedReport { name, url });
+                    if !dry_run {
+                        // The file may hold scopes and tokens, so only the line goes.
+                        let mut npmrc = Npmrc::load(&npmrc_path)?;
+                        if npmrc.remove("registry") {
+                            npmrc.save(&npmrc_path)?;
+                            if !json {
+                                println!("Removed registry from local .npmrc");
                             }
                         }
-                        let content = if lines.is_empty() {
-                            "".to_string()
-                        } else {
-                            lines.join("\n") + "\n"
-                        };
-                        if content.is_empty() {
-                            fs::remove_file(&npmrc_path)?;
-                            println!("Removed local .npmrc (no non-registry lines)");
-                        } else {
-                            fs::write(&npmrc_path, content)?;
-                            println!("Removed registry from local .npmrc");
-                        }
+                    } else if !json {
+                        println!("Dry-run: Would remove registry from local .npmrc");
                     }
-                } else {
-                    println!("Dry-run: Would remove registry from local .npmrc");
                 }
             }
-        } else {
-            println!("{}", "No registry found in local .npmrc".yellow());
+            if !json {
+                print_kept(&report.kept, min_failures);
+            }
         }
-    } else {
-        let mut unreachable = Vec::new();
-        let mut registries: Vec<_> = config.custom_registries.keys().cloned().collect();
-        registries.sort();
-        for name in registries {
-            let (ok, duration) = config.test_registry(&name)?;
-            let time = duration.map(|d| format!(" ({}ms)", d)).unwrap_or_default();
-            if !ok {
-                unreachable.push(name.clone());
-                println!(
-                    "{} {} - {}{}",
-                    "🔴 Unreachable:".red(),
-                    name,
-                    config.custom_registries[&name],
-                    time
-                );
-            } else {
-                println!(
-                    "{} {} - {}{}",
-                    "✅ Reachable:".green(),
+        report.results.push(probe);
+        return if json { print_json(&report) } else { Ok(()) };
+    }
+
+    let mut registries: Vec<_> = config
+        .registries
+        .iter()
+        .filter(|registry| registry.origin == Origin::Custom)
+        .map(|registry| registry.name.clone())
+        .collect();
+    registries.sort();
+    let targets: Vec<_> = registries
+        .iter()
+        .filter_map(|name| config.probe_target(name, &options))
+        .collect();
+    let results = prober.probe_all(&targets, 1);
+    let _lock = lock_config()?;
+    // Re-read now that the lock is held: another nrs may have run meanwhile.
+    let mut config = Config::load()?;
+    let mut history = History::load()?;
+    for (target, result) in targets.into_iter().zip(results) {
+        let probe = ProbeReport::new(target, result);
+        history.record(&probe);
+        let name = probe.name.clone().unwrap_or_default();
+        if !json {
+            println!(
+                "{} - {} - {} ({}ms)",
+                name,
+                probe.url,
+                health_label(probe.health, format),
+                probe.latency_ms.unwrap_or_default()
+            );
+        }
+        if probe.health.is_down() {
+            // One failed probe proves little (VPN-only registries, flaky Wi-Fi), so
+            // only remove registries that kept failing over the window.
+            let failures = history.consecutive_failures(&name, &probe.url, window);
+            match keep_reason(&config, Some(&name), failures, min_failures) {
+                Some(reason) => report.kept.push(KeptReport {
                     name,
-                    config.custom_registries[&name],
-                    time
-                );
+                    reason,
+                    failures,
+                }),
+                None => report.removed.push(RemovedReport {
+                    name: Some(name),
+                    url: probe.url.clone(),
+                }),
             }
         }
-        if unreachable.is_empty() {
+        report.results.push(probe);
+    }
+    history.save()?;
+
+    if !json {
+        print_kept(&report.kept, min_failures);
+    }
+
+    let unreachable: Vec<String> = report
+        .removed
+        .iter()
+        .filter_map(|removed| removed.name.clone())
+        .collect();
+    if unreachable.is_empty() {
+        if !json && report.kept.is_empty() {
             println!("{}", "All custom registries are reachable".green());
-        } else if dry_run {
+        }
+    } else if dry_run {
+        if !json {
             println!(
-                "{} Would remove {} unreachable custom registries: {}",
+                "{} Would move {} unreachable custom registries to the trash: {}",
                 "Dry-run:".yellow(),
                 unreachable.len(),
                 unreachable.join(", ")
             );
-        } else {
-            for name in &unreachable {
-                config.remove_registry(name)?;
+        }
+    } else {
+        let mut moved = Vec::new();
+        for name in unreachable {
+            // Skip registries a parallel nrs removed while the probes ran.
+            if config.get(&name).is_none() {
+                continue;
             }
+            config.trash_registry(&name)?;
+            moved.push(name);
+        }
+        report.removed.retain(|removed| {
+            removed
+                .name
+                .as_ref()
+                .is_none_or(|name| moved.contains(name))
+        });
+        if !json && !moved.is_empty() {
             println!(
-                "Removed {} unreachable custom registries: {}",
-                unreachable.len(),
-                unreachable.join(", ")
+                "Moved {} unreachable custom registries to the trash: {} (undo with nrs restore <name>)",
+                moved.len(),
+                moved.join(", ")
             );
-            if config.current.is_none() {
-                let npmrc_path = get_npmrc_path()?;
-                if npmrc_path.exists() {
-                    let mut lines = Vec::new();
-                    let file = File::open(&npmrc_path)?;
-                    let reader = BufReader::new(file);
-                    for line in reader.lines() {
-                        let line = line?;
-                        if !line.trim().starts_with("registry=") {
-                            lines.push(line);
-                        }
-                    }
-                    let content = if lines.is_empty() {
-                        "".to_string()
-                    } else {
-                        lines.join("\n") + "\n"
-                    };
-                    if content.is_empty() {
-                        fs::remove_file(&npmrc_path)?;
-                    } else {
-                        fs::write(&npmrc_path, content)?;
-                    }
-                }
-            }
         }
     }
-    Ok(())
+    if json { print_json(&report) } else { Ok(()) }
+}
+
+/// Why a down registry stays: `current`, `keep` or `not-enough-failures`.
+fn keep_reason(
+    config: &Config,
+    name: Option<&str>,
+    failures: usize,
+    min_failures: usize,
+) -> Option<&'static str> {
+    if name.is_some() && config.current.as_deref() == name {
+        Some("current")
+    } else if name
+        .and_then(|name| config.get(name))
+        .is_some_and(|registry| registry.keep)
+    {
+        Some("keep")
+    } else if failures < min_failures {
+        Some("not-enough-failures")
+    } else {
+        None
+    }
+}
+
+fn print_kept(kept: &[KeptReport], min_failures: usize) {
+    for kept in kept {
+        let why = match kept.reason {
+            "current" => "it is the current registry".to_string(),
+            "keep" => "it is marked keep".to_string(),
+            _ => format!("{} of {} consecutive failures", kept.failures, min_failures),
+        };
+        println!("{} {} ({})", "Keeping".yellow(), kept.name, why);
+    }
 }
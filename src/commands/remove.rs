@@ -1,7 +1,25 @@
-use crate::{config::Config, error::NrsError};
+use serde::Serialize;
 
-pub fn execute(mut config: Config, name: &str) -> Result<(), NrsError> {
-    config.remove_registry(&name)?;
+use crate::{
+    config::Config,
+    error::NrsError,
+    output::{OutputFormat, print_json},
+};
+
+#[derive(Serialize)]
+struct RemoveReport<'a> {
+    action: &'static str,
+    name: &'a str,
+}
+
+pub fn execute(mut config: Config, name: &str, format: OutputFormat) -> Result<(), NrsError> {
+    config.remove_registry(name)?;
+    if format.is_json() {
+        return print_json(&RemoveReport {
+            action: "remove",
+            name,
+        });
+    }
     println!("Removed registry: {}", name);
     Ok(())
 }
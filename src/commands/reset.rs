@@ -1,21 +1,65 @@
-use crate::{config::Config, error::NrsError};
+use crate::{
+    config::Config,
+    error::NrsError,
+    output::{OutputFormat, print_json},
+    registry::Origin,
+};
 use colored::Colorize;
+use serde::Serialize;
 
-pub fn execute(config: Config, yes: bool, all: bool) -> Result<(), NrsError> {
+#[derive(Serialize)]
+struct ResetReport {
+    action: &'static str,
+    reset: bool,
+    all: bool,
+}
+
+pub fn execute(config: Config, yes: bool, all: bool, format: OutputFormat) -> Result<(), NrsError> {
     if yes {
         let mut new_config = Config::new();
         if !all {
-            new_config.custom_registries = config.custom_registries.clone();
-            new_config.registry_order = new_config
+            new_config.registries = config
                 .registries
-                .keys()
-                .chain(new_config.custom_registries.keys())
+                .iter()
+                .filter(|registry| registry.origin == Origin::Custom)
                 .cloned()
                 .collect();
-            new_config.registry_order.sort();
             new_config.current = config.current.clone();
+            new_config.packs = config.packs.clone();
+            new_config.merge_presets();
         }
+        // Scopes and tokens stay with the registries that keep their name and
+        // URL; the npmrc lines of the others are removed along with them.
+        let survives = |name: &str| {
+            !all && config
+                .get_url(name)
+                .is_some_and(|url| new_config.get_url(name) == Some(url))
+        };
+        config.remove_npmrc_lines(survives)?;
+        let scopes = config
+            .scopes
+            .iter()
+            .filter(|(_, name)| survives(name))
+            .map(|(scope, name)| (scope.clone(), name.clone()))
+            .collect();
+        let auth = config
+            .auth
+            .iter()
+            .filter(|(name, _)| survives(name))
+            .map(|(name, token)| (name.clone(), token.clone()))
+            .collect();
+        new_config.scopes = scopes;
+        new_config.auth = auth;
         new_config.save()?;
+    }
+    if format.is_json() {
+        return print_json(&ResetReport {
+            action: "reset",
+            reset: yes,
+            all,
+        });
+    }
+    if yes {
         println!("{}", "Reset complete".yellow());
     } else {
         println!("{}", "Use --yes to confirm reset.".red());
@@ -0,0 +1,82 @@
+use std::time::Duration;
+
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::{
+    config::{Config, TrashedRegistry},
+    error::NrsError,
+    history::{History, format_age},
+    output::{OutputFormat, print_json},
+    utils::unix_now,
+};
+
+#[derive(Serialize)]
+struct RestoreReport {
+    action: &'static str,
+    registry: TrashReport,
+}
+
+/// A trash entry without its credentials.
+#[derive(Serialize)]
+struct TrashReport {
+    name: String,
+    url: String,
+    removed_at: u64,
+    scopes: Vec<String>,
+}
+
+impl From<&TrashedRegistry> for TrashReport {
+    fn from(entry: &TrashedRegistry) -> Self {
+        TrashReport {
+            name: entry.registry.name.clone(),
+            url: entry.registry.url.clone(),
+            removed_at: entry.removed_at,
+            scopes: entry.scopes.clone(),
+        }
+    }
+}
+
+/// Restores a pruned registry, or lists the trash when no name is given.
+pub fn execute(
+    mut config: Config,
+    name: Option<&str>,
+    format: OutputFormat,
+) -> Result<(), NrsError> {
+    let Some(name) = name else {
+        if format.is_json() {
+            let entries: Vec<TrashReport> = config.trash.iter().map(TrashReport::from).collect();
+            return print_json(&entries);
+        }
+        if config.trash.is_empty() {
+            println!("{}", "The trash is empty".yellow());
+        }
+        for entry in config.trash.iter().rev() {
+            let age = Duration::from_secs(unix_now().saturating_sub(entry.removed_at));
+            println!(
+                "   {:15} {} {}",
+                entry.registry.name,
+                entry.registry.url,
+                format!("(pruned {} ago)", format_age(age)).dimmed()
+            );
+        }
+        return Ok(());
+    };
+
+    let entry = config.restore_registry(name)?;
+    // Start the failure count from scratch so the next prune doesn't remove it again.
+    let mut history = History::load()?;
+    history.forget(name);
+    history.save()?;
+    if format.is_json() {
+        return print_json(&RestoreReport {
+            action: "restore",
+            registry: TrashReport::from(&entry),
+        });
+    }
+    println!(
+        "Restored registry: {} ({})",
+        entry.registry.name, entry.registry.url
+    );
+    Ok(())
+}
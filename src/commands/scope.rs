@@ -0,0 +1,121 @@
+use clap::{Subcommand, ValueHint};
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::{
+    config::Config,
+    error::NrsError,
+    output::{OutputFormat, print_json},
+    utils::NpmrcTarget,
+};
+
+#[derive(Subcommand)]
+pub enum ScopeAction {
+    /// Point a scope at a registry
+    Set {
+        #[arg(value_hint = ValueHint::Other)]
+        scope: String,
+        #[arg(value_hint = ValueHint::Other)]
+        name: String,
+        #[arg(long)]
+        local: bool,
+        #[arg(long, conflicts_with = "local")]
+        global_npmrc: bool,
+    },
+    /// List scope mappings
+    Ls,
+    /// Remove a scope mapping
+    Rm {
+        #[arg(value_hint = ValueHint::Other)]
+        scope: String,
+        #[arg(long)]
+        local: bool,
+        #[arg(long, conflicts_with = "local")]
+        global_npmrc: bool,
+    },
+}
+
+#[derive(Serialize)]
+struct ScopeReport<'a> {
+    action: &'static str,
+    scope: &'a str,
+    registry: Option<&'a str>,
+    url: Option<&'a str>,
+}
+
+pub fn execute(
+    mut config: Config,
+    action: ScopeAction,
+    format: OutputFormat,
+) -> Result<(), NrsError> {
+    match action {
+        ScopeAction::Set {
+            scope,
+            name,
+            local,
+            global_npmrc,
+        } => {
+            let target = NpmrcTarget::from_flags(local, global_npmrc);
+            let scope = config.set_scope(&scope, &name, target)?;
+            if format.is_json() {
+                return print_json(&ScopeReport {
+                    action: "set",
+                    scope: &scope,
+                    registry: Some(&name),
+                    url: config.get_url(&name).map(String::as_str),
+                });
+            }
+            println!(
+                "Scope {} now uses registry: {} ({})",
+                scope.bold(),
+                name.green(),
+                target.label()
+            );
+        }
+        ScopeAction::Ls => {
+            let mut scopes: Vec<_> = config.scopes.iter().collect();
+            scopes.sort();
+            if format.is_json() {
+                let reports: Vec<_> = scopes
+                    .iter()
+                    .map(|(scope, name)| ScopeReport {
+                        action: "ls",
+                        scope,
+                        registry: Some(name),
+                        url: config.get_url(name).map(String::as_str),
+                    })
+                    .collect();
+                return print_json(&reports);
+            }
+            if scopes.is_empty() {
+                println!("{}", "No scopes configured".yellow());
+                return Ok(());
+            }
+            for (scope, name) in scopes {
+                let url = config
+                    .get_url(name)
+                    .map(String::as_str)
+                    .unwrap_or("(unknown registry)");
+                println!("{:15} {:15} {}", scope, name, url);
+            }
+        }
+        ScopeAction::Rm {
+            scope,
+            local,
+            global_npmrc,
+        } => {
+            let target = NpmrcTarget::from_flags(local, global_npmrc);
+            let scope = config.remove_scope(&scope, target)?;
+            if format.is_json() {
+                return print_json(&ScopeReport {
+                    action: "rm",
+                    scope: &scope,
+                    registry: None,
+                    url: None,
+                });
+            }
+            println!("Removed scope: {}", scope);
+        }
+    }
+    Ok(())
+}
@@ -1,16 +1,53 @@
 use std::fs;
 
 use colored::Colorize;
+use serde::Serialize;
 
-use crate::{error::NrsError, utils::get_npmrc_path_local};
+use crate::{
+    config::AuthToken,
+    error::NrsError,
+    npmrc::Npmrc,
+    output::{OutputFormat, print_json},
+    utils::{NpmrcTarget, get_npmrc_path_for},
+};
 
-pub fn execute(local: bool) -> Result<(), NrsError> {
-    let npmrc_path = get_npmrc_path_local(local)?;
-    if npmrc_path.exists() {
-        let content = fs::read_to_string(npmrc_path)?;
-        println!("{}", content);
+#[derive(Serialize)]
+struct ShowReport {
+    path: String,
+    exists: bool,
+    content: Option<String>,
+}
+
+pub fn execute(target: NpmrcTarget, format: OutputFormat) -> Result<(), NrsError> {
+    let npmrc_path = get_npmrc_path_for(target)?;
+    let content = if npmrc_path.exists() {
+        Some(fs::read_to_string(&npmrc_path)?)
     } else {
-        println!("{}", "No .npmrc file found".yellow());
+        None
+    };
+    if format.is_json() {
+        return print_json(&ShowReport {
+            path: npmrc_path.display().to_string(),
+            exists: content.is_some(),
+            content: content.as_deref().map(mask_secrets),
+        });
+    }
+    match content {
+        Some(content) => println!("{}", content),
+        None => println!("{}", "No .npmrc file found".yellow()),
     }
     Ok(())
 }
+
+/// Masks the values of credential keys (`_authToken`, `_auth`, `_password`),
+/// like `nrs auth` does, leaving `${VAR}` references readable.
+fn mask_secrets(content: &str) -> String {
+    let mut npmrc = Npmrc::parse(content);
+    npmrc.replace_values(|key, value| {
+        let secret = ["_authToken", "_auth", "_password"]
+            .iter()
+            .any(|suffix| key.ends_with(suffix));
+        (secret && !value.starts_with("${")).then(|| AuthToken::Token(value.to_string()).masked())
+    });
+    npmrc.to_string()
+}
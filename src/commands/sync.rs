@@ -0,0 +1,103 @@
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::{
+    config::{Config, Drift},
+    error::NrsError,
+    output::{OutputFormat, drift_text, print_json},
+    utils::{NpmrcTarget, get_npmrc_path_for},
+};
+
+#[derive(Serialize)]
+struct SyncReport {
+    /// `check`, `adopt` or `apply`.
+    action: &'static str,
+    npmrc: String,
+    /// The disagreement found before syncing, if any.
+    drift: Option<Drift>,
+    current: Option<String>,
+    /// Set when `--adopt` added the npmrc registry as a custom registry.
+    added: Option<String>,
+}
+
+/// Reports drift between nrs and an npmrc, and with `--adopt` or `--apply`
+/// resolves it in one direction or the other.
+pub fn execute(
+    mut config: Config,
+    adopt: bool,
+    apply: bool,
+    target: NpmrcTarget,
+    format: OutputFormat,
+) -> Result<(), NrsError> {
+    let drift = config.drift(target)?;
+    let mut report = SyncReport {
+        action: "check",
+        npmrc: get_npmrc_path_for(target)?.display().to_string(),
+        drift: None,
+        current: None,
+        added: None,
+    };
+
+    if drift.is_none() {
+        report.current = config.current.clone();
+        if format.is_json() {
+            return print_json(&report);
+        }
+        println!("{} {} matches nrs", "In sync:".green(), target.label());
+        return Ok(());
+    }
+
+    if adopt {
+        report.action = "adopt";
+        if let Some((name, added)) = config.adopt_npmrc(target)? {
+            if added {
+                report.added = Some(name.clone());
+            }
+            if !format.is_json() {
+                if added {
+                    println!(
+                        "Added registry: {} ({})",
+                        name,
+                        config.get_url(&name).unwrap()
+                    );
+                }
+                println!("Adopted {} from {}", name.green().bold(), target.label());
+            }
+        } else if !format.is_json() {
+            println!(
+                "{}",
+                format!("No registry found in {}", target.label()).yellow()
+            );
+        }
+    } else if apply {
+        report.action = "apply";
+        match &config.current {
+            Some(current) => {
+                config.update_npmrc(false, target)?;
+                if !format.is_json() {
+                    println!("Wrote {} to {}", current.green().bold(), target.label());
+                }
+            }
+            None => {
+                if !format.is_json() {
+                    println!("{}", "No registry selected, nothing to apply".yellow());
+                }
+            }
+        }
+    } else if !format.is_json() {
+        if let Some(drift) = &drift {
+            println!("{} {}", "Drift:".yellow(), drift_text(drift, target));
+        }
+        println!(
+            "Run nrs sync --adopt to take the npmrc registry, or --apply to rewrite the npmrc"
+        );
+    }
+
+    report.drift = drift;
+    report.current = config.current.clone();
+    if format.is_json() {
+        print_json(&report)
+    } else {
+        Ok(())
+    }
+}
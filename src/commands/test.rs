@@ -1,91 +1,163 @@
-use std::time::Instant;
+use std::time::Duration;
 
-use crate::{config::Config, error::NrsError, utils::get_local_registry_url};
+use crate::{
+    config::Config,
+    error::NrsError,
+    history::History,
+    output::{OutputFormat, ProbeReport, health_label, print_json},
+    probe::{Health, ProbeOptions, Prober},
+    utils::{NpmrcTarget, get_registry_url},
+};
 use colored::Colorize;
 
-pub fn execute(config: Config, name: &str, local: bool) -> Result<(), NrsError> {
+pub fn execute(
+    config: Config,
+    name: &str,
+    local: bool,
+    samples: usize,
+    max_age: Option<Duration>,
+    options: ProbeOptions,
+    format: OutputFormat,
+) -> Result<(), NrsError> {
+    let prober = Prober::new(options)?;
+
     if local {
-        let Some(url) = get_local_registry_url()? else {
+        let Some(url) = get_registry_url(NpmrcTarget::Project)? else {
+            if format.is_json() {
+                return print_json(&Vec::<ProbeReport>::new());
+            }
             println!("{}", "No local .npmrc or registry found".yellow());
             return Ok(());
         };
-        let client = reqwest::blocking::Client::new();
-        let start = Instant::now();
-        let response = client.head(&url).send();
-        let duration = start.elapsed().as_millis();
-        match response {
-            Ok(resp) if resp.status().is_success() => {
-                println!(
-                    "{} {} ({}ms)",
-                    "✅ Local registry is reachable:".green(),
-                    url,
-                    duration
-                );
-            }
-            _ => {
-                println!(
-                    "{} {} ({}ms)",
-                    "🔴 Local registry is NOT reachable:".red(),
-                    url,
-                    duration
-                );
-            }
+        let target = config.probe_target_for_url(&url, &options);
+        let result = prober.sample(&target, samples);
+        let report = ProbeReport::new(target, result);
+        if format.is_json() {
+            return print_json(&vec![report]);
         }
-        Ok(())
-    } else if name.is_empty() {
-        let mut registries: Vec<_> = config
-            .registries
-            .keys()
-            .chain(config.custom_registries.keys())
-            .cloned()
-            .collect();
+        println!(
+            "{} {} - {}{}",
+            "Local registry:".bold(),
+            report.url,
+            health_label(report.health, format),
+            latency_text(&report)
+        );
+        return Ok(());
+    }
+
+    let names: Vec<String> = if name.is_empty() {
+        let mut registries = config.enabled_names();
         registries.sort();
-        for name in registries {
-            let (ok, duration) = config.test_registry(&name)?;
-            let status = if ok {
-                format!("{}", "✅ OK".green())
-            } else {
-                format!("{}", "🔴 Failed".red())
-            };
-            let time = duration.map(|d| format!(" ({}ms)", d)).unwrap_or_default();
-            let current = if config.current.as_deref() == Some(&name) {
-                format!("{}", "🟢".green()).to_string()
-            } else {
-                "".to_string()
-            };
-            let url = config
-                .registries
-                .get(&name)
-                .or_else(|| config.custom_registries.get(&name))
-                .unwrap();
-            println!("{:2} {:15} {} - {}{}", current, name, status, url, time);
-        }
-        Ok(())
+        registries
     } else {
-        let (ok, duration) = config.test_registry(name)?;
-        let status = if ok {
-            format!("{}", "✅ OK".green())
-        } else {
-            format!("{}", "🔴 Failed".red())
-        };
-        let time = duration.map(|d| format!(" ({}ms)", d)).unwrap_or_default();
-        let url = config
-            .registries
-            .get(name)
-            .or_else(|| config.custom_registries.get(name))
-            .ok_or_else(|| NrsError::RegistryNotFound(name.to_string()))?;
-        println!(
-            "{:2} {:15} {} - {}{}",
-            if config.current.as_deref() == Some(name) {
-                format!("{}", "🟢".green()).to_string()
+        vec![name.to_string()]
+    };
+    let targets = names
+        .iter()
+        .map(|name| {
+            config
+                .probe_target(name, &options)
+                .ok_or_else(|| NrsError::RegistryNotFound(name.to_string()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // With --cached, registries probed recently enough are answered from the history.
+    let mut history = History::load()?;
+    let (cached, targets): (Vec<_>, Vec<_>) = targets.into_iter().partition(|target| {
+        max_age.is_some_and(|max_age| {
+            let name = target.name.as_deref().unwrap_or_default();
+            history.fresh(name, &target.url, max_age).is_some()
+        })
+    });
+    let results = prober.probe_all(&targets, samples);
+    let mut reports: Vec<ProbeReport> = targets
+        .into_iter()
+        .zip(results)
+        .map(|(target, result)| ProbeReport::new(target, result))
+        .collect();
+    if !reports.is_empty() {
+        history = History::append(&reports)?;
+    }
+    reports.extend(cached.into_iter().filter_map(|target| {
+        let record = history.last(target.name.as_deref()?)?.clone();
+        Some(ProbeReport::from_record(target, &record))
+    }));
+    reports.sort_by_key(|report| {
+        names
+            .iter()
+            .position(|name| report.name.as_ref() == Some(name))
+    });
+    if format.is_json() {
+        return print_json(&reports);
+    }
+    for report in &reports {
+        print_probe(&config, report, format);
+    }
+    if samples > 1 && reports.len() > 1 {
+        print_ranking(&reports);
+    }
+    Ok(())
+}
+
+fn latency_text(report: &ProbeReport) -> String {
+    match (&report.stats, report.latency_ms) {
+        (Some(stats), _) => format!(
+            " (min {}ms, median {}ms, p95 {}ms, jitter {:.1}ms, cold {}ms{})",
+            stats.min_ms,
+            stats.median_ms,
+            stats.p95_ms,
+            stats.jitter_ms,
+            stats.cold_ms,
+            if stats.failures > 0 {
+                format!(", {}/{} failed", stats.failures, stats.samples)
             } else {
-                "".to_string()
-            },
+                String::new()
+            }
+        ),
+        (None, Some(ms)) if report.cached => format!(" ({}ms, cached)", ms),
+        (None, Some(ms)) => format!(" ({}ms)", ms),
+        (None, None) => String::new(),
+    }
+}
+
+/// Healthy registries ordered by median latency, then p95.
+fn print_ranking(reports: &[ProbeReport]) {
+    let mut ranked: Vec<_> = reports
+        .iter()
+        .filter(|report| report.health == Health::Up)
+        .filter_map(|report| Some((report.name.as_deref()?, report.stats.as_ref()?)))
+        .collect();
+    if ranked.is_empty() {
+        return;
+    }
+    ranked.sort_by_key(|(_, stats)| (stats.median_ms, stats.p95_ms));
+    println!();
+    println!("{}", "Ranking (median latency):".bold());
+    for (position, (name, stats)) in ranked.iter().enumerate() {
+        println!(
+            "{:>3}. {:15} {}ms (p95 {}ms)",
+            position + 1,
             name,
-            status,
-            url,
-            time
+            stats.median_ms,
+            stats.p95_ms
         );
-        Ok(())
     }
 }
+
+fn print_probe(config: &Config, report: &ProbeReport, format: OutputFormat) {
+    let name = report.name.as_deref().unwrap_or_default();
+    let time = latency_text(report);
+    let current = if config.current.as_deref() == Some(name) {
+        format!("{}", format.icon("🟢", "*").green())
+    } else {
+        "".to_string()
+    };
+    println!(
+        "{:2} {:15} {} - {}{}",
+        current,
+        name,
+        health_label(report.health, format),
+        report.url,
+        time
+    );
+}
@@ -1,15 +1,167 @@
-use crate::{config::Config, error::NrsError};
+use colored::Colorize;
+use serde::Serialize;
 
-pub fn execute(mut config: Config, name: &str, backup: bool, local: bool) -> Result<(), NrsError> {
-    config.set_current(name, backup, local)?;
+use crate::{
+    config::Config,
+    error::NrsError,
+    history::History,
+    output::{OutputFormat, ProbeReport, RegistryInfo, health_label, print_json},
+    probe::{Health, ProbeOptions, Prober},
+    utils::{NpmrcTarget, get_npmrc_path_for, lock_config},
+};
+
+#[derive(Serialize)]
+struct UseReport {
+    action: &'static str,
+    registry: Option<RegistryInfo>,
+    npmrc: String,
+    /// Every probed candidate, only present with `--fastest`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    candidates: Option<Vec<ProbeReport>>,
+}
+
+pub fn execute(
+    mut config: Config,
+    name: &str,
+    backup: bool,
+    target: NpmrcTarget,
+    format: OutputFormat,
+) -> Result<(), NrsError> {
+    config.set_current(name, backup, target)?;
+    if format.is_json() {
+        return print_json(&UseReport {
+            action: "use",
+            registry: RegistryInfo::new(&config, name),
+            npmrc: get_npmrc_path_for(target)?.display().to_string(),
+            candidates: None,
+        });
+    }
+    println!("Switched to registry: {} ({})", name, target.label());
+    Ok(())
+}
+
+/// The candidates of `use --fastest` and how many probes each gets.
+pub struct Race<'a> {
+    /// Explicit candidates from `--among`; every enabled registry when empty.
+    pub among: &'a [String],
+    /// Only registries with this tag take part.
+    pub tag: Option<&'a str>,
+    pub samples: usize,
+}
+
+/// Probes the candidates in parallel and switches to the healthy one with the
+/// lowest median latency, breaking ties on p95. Probes run without the config
+/// lock, which is only taken to record the results and switch, so parallel
+/// nrs commands aren't held up by slow registries.
+pub fn fastest(
+    config: Config,
+    race: Race,
+    backup: bool,
+    target: NpmrcTarget,
+    options: ProbeOptions,
+    format: OutputFormat,
+) -> Result<(), NrsError> {
+    let Race {
+        among,
+        tag,
+        samples,
+    } = race;
+    let names: Vec<String> = if among.is_empty() {
+        let mut names: Vec<String> = config
+            .enabled_names()
+            .into_iter()
+            .filter(|name| {
+                tag.is_none_or(|tag| {
+                    config
+                        .get(name)
+                        .is_some_and(|registry| registry.has_tag(tag))
+                })
+            })
+            .collect();
+        names.sort();
+        names
+    } else {
+        // Disabled registries can't be selected, so they don't take part.
+        among
+            .iter()
+            .filter(|name| !config.get(name).is_some_and(|registry| registry.disabled))
+            .cloned()
+            .collect()
+    };
+    if let Some(tag) = tag
+        && names.is_empty()
+    {
+        return Err(NrsError::NoTaggedRegistry(tag.to_string()));
+    }
+    let targets = names
+        .iter()
+        .map(|name| {
+            config
+                .probe_target(name, &options)
+                .ok_or_else(|| NrsError::RegistryNotFound(name.to_string()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let results = Prober::new(options)?.probe_all(&targets, samples);
+    let reports: Vec<ProbeReport> = targets
+        .into_iter()
+        .zip(results)
+        .map(|(target, result)| ProbeReport::new(target, result))
+        .collect();
+    History::append(&reports)?;
+
+    let mut healthy: Vec<&ProbeReport> = reports
+        .iter()
+        .filter(|report| report.health == Health::Up)
+        .collect();
+    healthy.sort_by_key(|report| {
+        let median = report.latency_ms.unwrap_or(u128::MAX);
+        let p95 = report.stats.as_ref().map_or(median, |stats| stats.p95_ms);
+        (median, p95)
+    });
+    let Some(best) = healthy.first() else {
+        let candidates = if among.is_empty() { &names[..] } else { among };
+        return Err(NrsError::NoHealthyRegistry(candidates.join(", ")));
+    };
+    let name = best.name.clone().unwrap_or_default();
+    let _lock = lock_config()?;
+    let mut config = Config::load()?;
+    config.set_current(&name, backup, target)?;
+
+    if format.is_json() {
+        return print_json(&UseReport {
+            action: "use",
+            registry: RegistryInfo::new(&config, &name),
+            npmrc: get_npmrc_path_for(target)?.display().to_string(),
+            candidates: Some(reports),
+        });
+    }
+
+    for report in &reports {
+        println!(
+            "   {:15} {} ({}ms)",
+            report.name.as_deref().unwrap_or_default(),
+            health_label(report.health, format),
+            report.latency_ms.unwrap_or_default()
+        );
+    }
+    let runner_up = healthy
+        .get(1)
+        .map(|report| {
+            format!(
+                ", next best {} at {}ms",
+                report.name.as_deref().unwrap_or_default(),
+                report.latency_ms.unwrap_or_default()
+            )
+        })
+        .unwrap_or_default();
     println!(
-        "Switched to registry: {} ({})",
-        name,
-        if local {
-            ".npmrc (local)"
-        } else {
-            ".npmrc (global)"
-        }
+        "Switched to registry: {} ({}) - fastest healthy of {} candidates, median {}ms{}",
+        name.green().bold(),
+        target.label(),
+        reports.len(),
+        best.latency_ms.unwrap_or_default(),
+        runner_up
     );
     Ok(())
 }
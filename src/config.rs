@@ -1,247 +1,991 @@
-use colored::*;
 use std::{
     collections::HashMap,
+    env,
     fs::{self, File},
-    io::{BufRead, BufReader, Read},
-    time::Instant,
+    io::Read,
+    path::Path,
+    time::Duration,
 };
 
 use serde::{Deserialize, Serialize};
 
 use crate::{
     error::NrsError,
-    utils::{derive_registry_name, get_config_path, get_npmrc_path, get_npmrc_path_local},
+    migrate::{CURRENT_VERSION, backup_if_outdated, migrate, version_of},
+    npmrc::Npmrc,
+    pin::Pin,
+    presets,
+    probe::{ProbeOptions, ProbeStrategy, ProbeTarget},
+    registry::{Origin, Registry},
+    utils::{
+        NpmrcTarget, auth_key, derive_registry_name, get_config_path, get_npmrc_path,
+        get_npmrc_path_for, get_registry_url, unix_now, write_atomic, write_private,
+    },
 };
 
 #[derive(Serialize, Deserialize)]
 pub struct Config {
-    pub registries: HashMap<String, String>,
-    pub custom_registries: HashMap<String, String>,
-    pub registry_order: Vec<String>,
+    /// Schema version, see [`crate::migrate`].
+    pub version: u32,
+    /// Every registry, in display order. Presets are merged in on load and
+    /// only overrides of them, plus the user's own registries, are stored.
+    #[serde(serialize_with = "presets::serialize_overrides")]
+    pub registries: Vec<Registry>,
+    /// Presets the user removed.
+    #[serde(default)]
+    pub removed_presets: Vec<String>,
+    /// Preset packs whose presets are listed, see [`presets::PACKS`].
+    #[serde(default = "presets::default_packs")]
+    pub packs: Vec<String>,
     pub current: Option<String>,
+    #[serde(default)]
+    pub scopes: HashMap<String, String>,
+    #[serde(default)]
+    pub auth: HashMap<String, AuthToken>,
+    /// Registries removed by `prune`, most recent last, for `nrs restore`.
+    #[serde(default)]
+    pub trash: Vec<TrashedRegistry>,
 }
 
-impl Config {
-    pub fn new() -> Self {
-        let mut registries = HashMap::new();
-        let mut registry_order = Vec::new();
+/// A pruned registry together with the settings needed to restore it.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TrashedRegistry {
+    #[serde(flatten)]
+    pub registry: Registry,
+    /// Unix timestamp, in seconds.
+    pub removed_at: u64,
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    pub auth: Option<AuthToken>,
+}
+
+/// The parts of `.nrsrc` needed to resolve a registry name, see [`Config::lookup`].
+#[derive(Deserialize)]
+struct StoredRegistries {
+    #[serde(default)]
+    registries: Vec<Registry>,
+    #[serde(default)]
+    removed_presets: Vec<String>,
+    #[serde(default = "presets::default_packs")]
+    packs: Vec<String>,
+}
+
+/// How an npmrc's `registry` disagrees with the nrs selection.
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum Drift {
+    /// The npmrc points at another known registry.
+    Known { name: String, url: String },
+    /// The npmrc points at a registry nrs doesn't know.
+    Unknown { url: String },
+    /// A registry is selected but the npmrc sets none.
+    Missing,
+}
 
-        let default_registries = [
-            ("npm", "https://registry.npmjs.org/"),
-            ("yarn", "https://registry.yarnpkg.com/"),
-            ("taobao", "https://registry.npmmirror.com/"),
-            ("tencent", "https://mirrors.cloud.tencent.com/npm/"),
-            ("npmMirror", "https://skimdb.npmjs.com/registry/"),
-            ("github", "https://npm.pkg.github.com/"),
-        ];
+/// Result of [`Config::add_registry`].
+pub enum AddOutcome {
+    Added,
+    /// The URL is already registered under this name.
+    Exists(String),
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthToken {
+    Token(String),
+    Env(String),
+}
+
+impl AuthToken {
+    /// Value written to `.npmrc`; env tokens are left for npm to expand.
+    pub fn npmrc_value(&self) -> String {
+        match self {
+            AuthToken::Token(token) => token.clone(),
+            AuthToken::Env(var) => format!("${{{}}}", var),
+        }
+    }
 
-        for (name, url) in default_registries {
-            registries.insert(name.to_string(), url.to_string());
-            registry_order.push(name.to_string());
+    pub fn masked(&self) -> String {
+        match self {
+            AuthToken::Token(token) if token.chars().count() > 8 => {
+                let head: String = token.chars().take(4).collect();
+                let tail: String = token.chars().skip(token.chars().count() - 4).collect();
+                format!("{}…{}", head, tail)
+            }
+            AuthToken::Token(_) => "****".to_string(),
+            AuthToken::Env(var) => format!("${{{}}}", var),
         }
+    }
+}
 
+impl Config {
+    pub fn new() -> Self {
         Config {
-            registries,
-            custom_registries: HashMap::new(),
-            registry_order,
+            version: CURRENT_VERSION,
+            registries: presets::merge(Vec::new(), &[], &presets::default_packs()),
+            removed_presets: Vec::new(),
+            packs: presets::default_packs(),
             current: None,
+            scopes: HashMap::new(),
+            auth: HashMap::new(),
+            trash: Vec::new(),
         }
     }
 
+    /// Reads `.nrsrc` without touching any file; see [`Config::drift`] and
+    /// [`Config::adopt_npmrc`] for reconciling it with the npmrc.
     pub fn load() -> Result<Self, NrsError> {
         let config_path = get_config_path()?;
         let mut config = if config_path.exists() {
             let mut file = File::open(&config_path)?;
             let mut contents = String::new();
             file.read_to_string(&mut contents)?;
-            serde_json::from_str(&contents)?
+            // Older layouts are upgraded in memory; the file is rewritten on the next save.
+            let mut config: Config =
+                serde_json::from_value(migrate(serde_json::from_str(&contents)?)?)?;
+            config.merge_presets();
+            config
         } else {
             Config::new()
         };
+        config.merge_project();
+        Ok(config)
+    }
 
-        if config.registry_order.is_empty() {
-            config.registry_order = config
-                .registries
-                .keys()
-                .chain(config.custom_registries.keys())
-                .cloned()
-                .collect();
-            config.registry_order.sort();
-        }
-
-        let npmrc_path = get_npmrc_path()?;
-        if npmrc_path.exists() {
-            let file = File::open(&npmrc_path)?;
-            let reader = BufReader::new(file);
-            for line in reader.lines() {
-                let line = line?;
-                if line.trim().starts_with("registry=") {
-                    let url = line
-                        .trim()
-                        .strip_prefix("registry=")
-                        .unwrap_or("")
-                        .to_string();
-                    let mut found = false;
-                    // Check default registries
-                    for (name, reg_url) in &config.registries {
-                        if reg_url == &url {
-                            config.current = Some(name.clone());
-                            found = true;
-                            break;
-                        }
-                    }
-                    // Check custom registries
-                    if !found {
-                        for (name, reg_url) in &config.custom_registries {
-                            if reg_url == &url {
-                                config.current = Some(name.clone());
-                                found = true;
-                                break;
-                            }
-                        }
-                    }
-                    // Add unknown registry to custom_registries
-                    if !found && !url.is_empty() {
-                        let name = derive_registry_name(&url, &config.custom_registries);
-                        config.custom_registries.insert(name.clone(), url.clone());
-                        if !config.registry_order.contains(&name) {
-                            config.registry_order.push(name.clone());
-                        }
-                        config.current = Some(name);
-                    }
-                    break;
-                }
+    /// Resolves `name` the way [`Config::load`] would inside `pin`'s project,
+    /// but only reads the stored records: presets are looked up by name and
+    /// the pin is not read again. Meant for the shell hook, which runs on
+    /// every directory change.
+    pub fn lookup(name: &str, pin: &Pin) -> Result<Registry, NrsError> {
+        let config_path = get_config_path()?;
+        let stored: StoredRegistries = if config_path.exists() {
+            let value: serde_json::Value =
+                serde_json::from_str(&fs::read_to_string(&config_path)?)?;
+            // Only files older nrs versions wrote need the full upgrade.
+            if version_of(&value) < CURRENT_VERSION {
+                serde_json::from_value(migrate(value)?)?
+            } else {
+                serde_json::from_value(value)?
             }
+        } else {
+            StoredRegistries {
+                registries: Vec::new(),
+                removed_presets: Vec::new(),
+                packs: presets::default_packs(),
+            }
+        };
+        let removed = stored.removed_presets.iter().any(|removed| removed == name);
+        let preset = presets::in_packs(&stored.packs).find(|preset| preset.name == name);
+        let own = stored.registries.into_iter().find(|registry| {
+            registry.name == name
+                && !(registry.origin == Origin::Builtin && preset.is_some() && removed)
+        });
+        own.or_else(|| preset.filter(|_| !removed).map(|preset| preset.registry()))
+            .or_else(|| {
+                pin.registries
+                    .iter()
+                    .find(|entry| entry.name == name)
+                    .map(|entry| {
+                        let mut registry = Registry::new(&entry.name, &entry.url, Origin::Project);
+                        entry.apply_details(&mut registry);
+                        registry
+                    })
+            })
+            .ok_or_else(|| NrsError::RegistryNotFound(name.to_string()))
+    }
+
+    /// Adds the registries defined by the pin file above the working directory
+    /// as a read-only project layer. The user's own registries win on a name
+    /// clash; project records are never saved, see [`Config::adopt_registry`].
+    /// A pin that can't be read is skipped with a warning, so it can't break
+    /// commands that don't need it, such as `nrs init --force`.
+    fn merge_project(&mut self) {
+        let Some(path) = env::current_dir().ok().and_then(|cwd| Pin::find(&cwd)) else {
+            return;
+        };
+        let pin = match Pin::load(&path) {
+            Ok(pin) => pin,
+            Err(err) => {
+                eprintln!("nrs: ignoring the project registries: {}", err);
+                return;
+            }
+        };
+        for entry in pin.registries {
+            if self.get(&entry.name).is_some() {
+                continue;
+            }
+            let mut registry = Registry::new(&entry.name, &entry.url, Origin::Project);
+            entry.apply_details(&mut registry);
+            self.registries.push(registry);
         }
+    }
 
-        config.save()?;
-        Ok(config)
+    /// Looks up `name` for a change saved to `~/.nrsrc`. Project registries
+    /// are refused, since the saved name would dangle outside the project.
+    fn user_registry(&self, name: &str) -> Result<&Registry, NrsError> {
+        let registry = self
+            .get(name)
+            .ok_or_else(|| NrsError::RegistryNotFound(name.to_string()))?;
+        if registry.origin == Origin::Project {
+            return Err(NrsError::ProjectRegistry(name.to_string()));
+        }
+        Ok(registry)
+    }
+
+    /// Makes a project registry one of the user's own, so it is saved to
+    /// `~/.nrsrc` and available outside the project.
+    pub fn adopt_registry(&mut self, name: &str) -> Result<AddOutcome, NrsError> {
+        let registry = self
+            .get(name)
+            .ok_or_else(|| NrsError::RegistryNotFound(name.to_string()))?;
+        if registry.origin != Origin::Project {
+            return Err(NrsError::NotProjectRegistry(name.to_string()));
+        }
+        let url = registry.url.trim_end_matches('/').to_string();
+        if let Some(existing) = self.registries.iter().find(|registry| {
+            registry.origin != Origin::Project && registry.url.trim_end_matches('/') == url
+        }) {
+            return Ok(AddOutcome::Exists(existing.name.clone()));
+        }
+        if let Some(registry) = self
+            .registries
+            .iter_mut()
+            .find(|registry| registry.name == name)
+        {
+            registry.origin = Origin::Custom;
+        }
+        self.save()?;
+        Ok(AddOutcome::Added)
+    }
+
+    /// Re-layers the stored records over the presets after `removed_presets`
+    /// or `packs` changed.
+    pub fn merge_presets(&mut self) {
+        self.registries = presets::merge(
+            std::mem::take(&mut self.registries),
+            &self.removed_presets,
+            &self.packs,
+        );
     }
 
     pub fn save(&self) -> Result<(), NrsError> {
         let config_path = get_config_path()?;
+        backup_if_outdated()?;
         let contents = serde_json::to_string_pretty(self)?;
-        fs::write(&config_path, contents)?;
+        if self.has_literal_tokens() {
+            write_private(&config_path, &contents)?;
+        } else {
+            write_atomic(&config_path, &contents)?;
+        }
         Ok(())
     }
 
-    pub fn set_current(&mut self, name: &str, backup: bool, local: bool) -> Result<(), NrsError> {
-        if !self.registries.contains_key(name) && !self.custom_registries.contains_key(name) {
-            return Err(NrsError::RegistryNotFound(name.to_string()));
+    /// Whether `.nrsrc` holds secrets, rather than only `$VAR` references.
+    pub fn has_literal_tokens(&self) -> bool {
+        self.auth
+            .values()
+            .any(|token| matches!(token, AuthToken::Token(_)))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Registry> {
+        self.registries
+            .iter()
+            .find(|registry| registry.name == name)
+    }
+
+    pub fn get_url(&self, name: &str) -> Option<&String> {
+        self.get(name).map(|registry| &registry.url)
+    }
+
+    /// Finds the registry name for a URL, ignoring a trailing slash.
+    pub fn name_for_url(&self, url: &str) -> Option<&str> {
+        let url = url.trim_end_matches('/');
+        self.registries
+            .iter()
+            .find(|registry| registry.url.trim_end_matches('/') == url)
+            .map(|registry| registry.name.as_str())
+    }
+
+    /// Names of the registries that are not disabled, in display order.
+    pub fn enabled_names(&self) -> Vec<String> {
+        self.registries
+            .iter()
+            .filter(|registry| !registry.disabled)
+            .map(|registry| registry.name.clone())
+            .collect()
+    }
+
+    /// Applies `change` to a registry's record and saves.
+    pub fn update_registry(
+        &mut self,
+        name: &str,
+        change: impl FnOnce(&mut Registry),
+    ) -> Result<(), NrsError> {
+        let registry = self
+            .registries
+            .iter_mut()
+            .find(|registry| registry.name == name)
+            .ok_or_else(|| NrsError::RegistryNotFound(name.to_string()))?;
+        if registry.origin == Origin::Project {
+            return Err(NrsError::ProjectRegistry(name.to_string()));
         }
-        self.current = Some(name.to_string());
-        self.save()?;
-        self.update_npmrc(backup, local)?;
-        Ok(())
+        change(registry);
+        self.save()
+    }
+
+    pub fn set_current(
+        &mut self,
+        name: &str,
+        backup: bool,
+        target: NpmrcTarget,
+    ) -> Result<(), NrsError> {
+        let registry = self
+            .get(name)
+            .ok_or_else(|| NrsError::RegistryNotFound(name.to_string()))?;
+        if registry.disabled {
+            return Err(NrsError::RegistryDisabled(name.to_string()));
+        }
+        // nrs tracks the user config's selection; project and global files are only written.
+        if target == NpmrcTarget::User {
+            self.user_registry(name)?;
+            self.current = Some(name.to_string());
+            self.save()?;
+        }
+        self.write_registry(name, backup, target)
     }
 
-    pub fn add_registry(&mut self, name: &str, url: &str) -> Result<(), NrsError> {
+    pub fn add_registry(&mut self, name: &str, url: &str) -> Result<AddOutcome, NrsError> {
         if !url.starts_with("http://") && !url.starts_with("https://") {
             return Err(NrsError::InvalidRegistryUrl(url.to_string()));
         }
         // Check if URL already exists
-        for (existing_name, existing_url) in
-            self.registries.iter().chain(self.custom_registries.iter())
-        {
-            if existing_url == url {
-                if existing_name != name {
-                    println!(
-                        "{} Registry URL {} already exists as {}. Use that name or edit it.",
-                        "Warning:".yellow(),
-                        url,
-                        existing_name
-                    );
-                }
-                return Ok(());
-            }
+        if let Some(existing) = self.registries.iter().find(|registry| registry.url == url) {
+            return Ok(AddOutcome::Exists(existing.name.clone()));
         }
-        self.custom_registries
-            .insert(name.to_string(), url.to_string());
-        if !self.registry_order.contains(&name.to_string()) {
-            self.registry_order.push(name.to_string());
+        if self.get(name).is_some() {
+            return Err(NrsError::RegistryExists(name.to_string()));
         }
+        self.registries
+            .push(Registry::new(name, url, Origin::Custom));
         self.save()?;
-        Ok(())
+        Ok(AddOutcome::Added)
     }
 
     pub fn edit_registry(&mut self, name: &str, new_url: &str) -> Result<(), NrsError> {
-        if !self.registries.contains_key(name) && !self.custom_registries.contains_key(name) {
-            return Err(NrsError::RegistryNotFound(name.to_string()));
-        }
         if !new_url.starts_with("https://") && !new_url.starts_with("http://") {
             return Err(NrsError::InvalidRegistryUrl(new_url.to_string()));
         }
-        if self.registries.contains_key(name) {
-            self.registries
-                .insert(name.to_string(), new_url.to_string());
-        } else {
-            self.custom_registries
-                .insert(name.to_string(), new_url.to_string());
+        let old_key = self.get_url(name).map(|url| auth_key(url));
+        self.update_registry(name, |registry| registry.url = new_url.to_string())?;
+        self.rewrite_scope_lines(name, Some(new_url))?;
+        if let Some(old_key) = old_key.filter(|_| self.auth.contains_key(name)) {
+            let mut changes = vec![(old_key, None)];
+            changes.extend(self.auth_changes(self.current.as_deref(), NpmrcTarget::User));
+            write_npmrc_keys(&get_npmrc_path()?, &changes)?;
         }
-        self.save()?;
         Ok(())
     }
 
     pub fn remove_registry(&mut self, name: &str) -> Result<(), NrsError> {
-        let was_default = self.registries.remove(name).is_some();
-        let was_custom = self.custom_registries.remove(name).is_some();
-        if !was_default && !was_custom {
-            return Err(NrsError::RegistryNotFound(name.to_string()));
+        let index = self
+            .registries
+            .iter()
+            .position(|registry| registry.name == name)
+            .ok_or_else(|| NrsError::RegistryNotFound(name.to_string()))?;
+        if self.registries[index].origin == Origin::Project {
+            return Err(NrsError::ProjectRegistry(name.to_string()));
+        }
+        let removed = self.registries.remove(index);
+        let removed_url = removed.url.clone();
+        if removed.origin == Origin::Builtin && !self.removed_presets.contains(&removed.name) {
+            self.removed_presets.push(removed.name);
         }
-        self.registry_order.retain(|n| n != name);
         if self.current.as_deref() == Some(name) {
             self.current = None;
         }
+        self.rewrite_scope_lines(name, None)?;
+        self.scopes.retain(|_, registry| registry != name);
+        // Drop the token line too, like `remove_auth`, now that the URL is known.
+        let changes: Vec<_> = self
+            .auth
+            .remove(name)
+            .map(|_| (auth_key(&removed_url), None))
+            .into_iter()
+            .collect();
         self.save()?;
-        Ok(())
+        write_npmrc_keys(&get_npmrc_path()?, &changes)
     }
 
-    pub fn update_npmrc(&self, backup: bool, local: bool) -> Result<(), NrsError> {
-        let npmrc_path = get_npmrc_path_local(local)?;
-        let new_registry = if let Some(current) = &self.current {
-            self.registries
-                .get(current)
-                .or_else(|| self.custom_registries.get(current))
-                .ok_or_else(|| NrsError::RegistryNotFound(current.to_string()))?
-        } else {
+    /// Points the `@scope:registry` lines of `name`'s scopes at `url`, or
+    /// removes them with `None`. Scopes don't record which npmrc they were
+    /// written to, so every target that has the line is updated.
+    fn rewrite_scope_lines(&self, name: &str, url: Option<&str>) -> Result<(), NrsError> {
+        let keys: Vec<String> = self
+            .scopes_for(name)
+            .iter()
+            .map(|scope| format!("{}:registry", scope))
+            .collect();
+        if keys.is_empty() {
             return Ok(());
+        }
+        let mut seen = Vec::new();
+        for target in [NpmrcTarget::User, NpmrcTarget::Project, NpmrcTarget::Global] {
+            let path = get_npmrc_path_for(target)?;
+            if seen.contains(&path) || !path.exists() {
+                continue;
+            }
+            let npmrc = Npmrc::load(&path)?;
+            let changes: Vec<_> = keys
+                .iter()
+                .filter(|key| npmrc.get(key).is_some())
+                .map(|key| (key.clone(), url.map(str::to_string)))
+                .collect();
+            write_npmrc_keys(&path, &changes)?;
+            seen.push(path);
+        }
+        Ok(())
+    }
+
+    /// Removes the `@scope:registry` and token lines of the registries `keep`
+    /// rejects from the npmrc files, before their scopes and tokens are forgotten.
+    pub fn remove_npmrc_lines(&self, keep: impl Fn(&str) -> bool) -> Result<(), NrsError> {
+        let mut names: Vec<&str> = self
+            .scopes
+            .values()
+            .chain(self.auth.keys())
+            .map(String::as_str)
+            .filter(|name| !keep(name))
+            .collect();
+        names.sort();
+        names.dedup();
+        for name in &names {
+            self.rewrite_scope_lines(name, None)?;
+        }
+        let changes: Vec<_> = names
+            .iter()
+            .filter(|name| self.auth.contains_key(**name))
+            .filter_map(|name| self.get_url(name))
+            .map(|url| (auth_key(url), None))
+            .collect();
+        write_npmrc_keys(&get_npmrc_path()?, &changes)
+    }
+
+    /// Removes a registry but keeps it, with its scopes and settings, in the trash.
+    pub fn trash_registry(&mut self, name: &str) -> Result<(), NrsError> {
+        let registry = self
+            .get(name)
+            .ok_or_else(|| NrsError::RegistryNotFound(name.to_string()))?
+            .clone();
+        let entry = TrashedRegistry {
+            registry,
+            removed_at: unix_now(),
+            scopes: self
+                .scopes_for(name)
+                .into_iter()
+                .map(str::to_string)
+                .collect(),
+            auth: self.auth.get(name).cloned(),
+        };
+        self.trash.push(entry);
+        self.remove_registry(name)
+    }
+
+    /// Brings back the most recently trashed registry called `name`, with its
+    /// scope and token lines in the user npmrc. Scopes that were mapped
+    /// elsewhere in the meantime are left alone.
+    pub fn restore_registry(&mut self, name: &str) -> Result<TrashedRegistry, NrsError> {
+        let index = self
+            .trash
+            .iter()
+            .rposition(|entry| entry.registry.name == name)
+            .ok_or_else(|| NrsError::NotInTrash(name.to_string()))?;
+        if self.get_url(name).is_some() {
+            return Err(NrsError::RegistryExists(name.to_string()));
+        }
+        let entry = self.trash.remove(index);
+        self.registries.push(entry.registry.clone());
+        for scope in &entry.scopes {
+            self.scopes
+                .entry(scope.clone())
+                .or_insert_with(|| name.to_string());
+        }
+        if let Some(auth) = &entry.auth {
+            self.auth.insert(name.to_string(), auth.clone());
+        }
+        self.save()?;
+        // Removing the registry dropped these lines from the npmrc; put them
+        // back where `scope set` and `auth set` write them.
+        let url = entry.registry.url.clone();
+        let mut changes: Vec<_> = self
+            .scopes_for(name)
+            .iter()
+            .map(|scope| (format!("{}:registry", scope), Some(url.clone())))
+            .collect();
+        changes.extend(self.auth_changes(self.current.as_deref(), NpmrcTarget::User));
+        write_npmrc_keys(&get_npmrc_path()?, &changes)?;
+        Ok(entry)
+    }
+
+    pub fn set_scope(
+        &mut self,
+        scope: &str,
+        name: &str,
+        target: NpmrcTarget,
+    ) -> Result<String, NrsError> {
+        let scope = normalize_scope(scope)?;
+        let url = self.user_registry(name)?.url.clone();
+        self.scopes.insert(scope.clone(), name.to_string());
+        self.save()?;
+        let mut changes = vec![(format!("{}:registry", scope), Some(url))];
+        changes.extend(self.auth_changes(self.current.as_deref(), target));
+        write_npmrc_keys(&get_npmrc_path_for(target)?, &changes)?;
+        Ok(scope)
+    }
+
+    pub fn remove_scope(&mut self, scope: &str, target: NpmrcTarget) -> Result<String, NrsError> {
+        let scope = normalize_scope(scope)?;
+        if self.scopes.remove(&scope).is_none() {
+            return Err(NrsError::ScopeNotFound(scope));
+        }
+        self.save()?;
+        let mut changes = vec![(format!("{}:registry", scope), None)];
+        changes.extend(self.auth_changes(self.current.as_deref(), target));
+        write_npmrc_keys(&get_npmrc_path_for(target)?, &changes)?;
+        Ok(scope)
+    }
+
+    pub fn set_auth(&mut self, name: &str, token: AuthToken) -> Result<(), NrsError> {
+        self.user_registry(name)?;
+        self.auth.insert(name.to_string(), token);
+        self.save()?;
+        write_npmrc_keys(
+            &get_npmrc_path()?,
+            &self.auth_changes(self.current.as_deref(), NpmrcTarget::User),
+        )
+    }
+
+    pub fn remove_auth(&mut self, name: &str) -> Result<(), NrsError> {
+        if !self.auth.contains_key(name) {
+            return Err(NrsError::AuthNotFound(name.to_string()));
+        }
+        // Compute the removal before forgetting the token so the line is cleaned up.
+        let changes: Vec<_> = self
+            .get_url(name)
+            .map(|url| (auth_key(url), None))
+            .into_iter()
+            .collect();
+        self.auth.remove(name);
+        self.save()?;
+        write_npmrc_keys(&get_npmrc_path()?, &changes)
+    }
+
+    /// `_authToken` lines for nrs-managed credentials: written for the selected
+    /// registry and scope targets, removed for every other registry.
+    fn auth_changes(
+        &self,
+        selected: Option<&str>,
+        target: NpmrcTarget,
+    ) -> Vec<(String, Option<String>)> {
+        self.auth_changes_for(
+            |name| selected == Some(name) || self.scopes.values().any(|registry| registry == name),
+            target != NpmrcTarget::User,
+        )
+    }
+
+    /// Token lines to write for the registries `active` accepts, and to remove
+    /// for every other registry with a stored token. A `shared` npmrc, such as
+    /// a project's, only gets `${VAR}` references: literal tokens are skipped
+    /// with a warning rather than written where they could be committed.
+    fn auth_changes_for(
+        &self,
+        active: impl Fn(&str) -> bool,
+        shared: bool,
+    ) -> Vec<(String, Option<String>)> {
+        let mut changes: Vec<_> = self
+            .auth
+            .iter()
+            .filter_map(|(name, token)| {
+                let url = self.get_url(name)?;
+                if !active(name) {
+                    return Some((auth_key(url), None));
+                }
+                if shared && let AuthToken::Token(_) = token {
+                    eprintln!(
+                        "nrs: not writing the literal token for {} to a shared npmrc \
+                         (store it with nrs auth set {} --token-env <VAR>)",
+                        name, name
+                    );
+                    return None;
+                }
+                Some((auth_key(url), Some(token.npmrc_value())))
+            })
+            .collect();
+        changes.sort();
+        changes
+    }
+
+    pub fn scopes_for(&self, name: &str) -> Vec<&str> {
+        let mut scopes: Vec<&str> = self
+            .scopes
+            .iter()
+            .filter(|(_, registry)| *registry == name)
+            .map(|(scope, _)| scope.as_str())
+            .collect();
+        scopes.sort();
+        scopes
+    }
+
+    /// Compares the registry set in the target npmrc with the nrs selection.
+    pub fn drift(&self, target: NpmrcTarget) -> Result<Option<Drift>, NrsError> {
+        let npmrc_url = get_registry_url(target)?.filter(|url| !url.is_empty());
+        let current_url = self.current.as_deref().and_then(|name| self.get_url(name));
+        Ok(match (npmrc_url, current_url) {
+            (Some(url), Some(current))
+                if url.trim_end_matches('/') == current.trim_end_matches('/') =>
+            {
+                None
+            }
+            (Some(url), _) => Some(match self.name_for_url(&url) {
+                Some(name) => Drift::Known {
+                    name: name.to_string(),
+                    url,
+                },
+                None => Drift::Unknown { url },
+            }),
+            (None, Some(_)) => Some(Drift::Missing),
+            (None, None) => None,
+        })
+    }
+
+    /// Takes the registry from the target npmrc into nrs, adding it as a custom
+    /// registry when unknown. For the user npmrc it also becomes the current
+    /// registry. Returns the name and whether it was added.
+    pub fn adopt_npmrc(&mut self, target: NpmrcTarget) -> Result<Option<(String, bool)>, NrsError> {
+        let Some(url) = get_registry_url(target)?.filter(|url| !url.is_empty()) else {
+            return Ok(None);
         };
+        let (name, added) = match self.name_for_url(&url) {
+            Some(name) => (name.to_string(), false),
+            None => {
+                let name = derive_registry_name(&url, |name| self.get(name).is_some());
+                self.registries
+                    .push(Registry::new(&name, &url, Origin::Custom));
+                (name, true)
+            }
+        };
+        if target == NpmrcTarget::User {
+            self.current = Some(name.clone());
+        }
+        self.save()?;
+        Ok(Some((name, added)))
+    }
+
+    /// Writes the current registry to the target npmrc.
+    pub fn update_npmrc(&self, backup: bool, target: NpmrcTarget) -> Result<(), NrsError> {
+        match &self.current {
+            Some(current) => self.write_registry(current, backup, target),
+            None => Ok(()),
+        }
+    }
+
+    fn write_registry(
+        &self,
+        name: &str,
+        backup: bool,
+        target: NpmrcTarget,
+    ) -> Result<(), NrsError> {
+        let npmrc_path = get_npmrc_path_for(target)?;
+        let new_registry = self
+            .get_url(name)
+            .ok_or_else(|| NrsError::RegistryNotFound(name.to_string()))?;
 
         if backup && npmrc_path.exists() {
             let backup_path = npmrc_path.with_extension("npmrc.bak");
             fs::copy(&npmrc_path, &backup_path)?;
         }
 
-        let mut lines = Vec::new();
-        if npmrc_path.exists() {
-            let file = File::open(&npmrc_path)?;
-            let reader = BufReader::new(file);
-            for line in reader.lines() {
-                let line = line?;
-                if !line.trim().starts_with("registry=") {
-                    lines.push(line);
+        let mut changes = vec![("registry".to_string(), Some(new_registry.clone()))];
+        changes.extend(self.auth_changes(Some(name), target));
+        write_npmrc_keys(&npmrc_path, &changes)
+    }
+
+    /// Writes a project pin to `npmrc_path`: its registry, its scope mappings
+    /// and the `${VAR}` token references they need. The project `.npmrc` is
+    /// usually committed, so literal tokens are left out with a warning.
+    /// `~/.nrsrc` is left alone, so the pin's scopes only apply to the project.
+    pub fn apply_pin(&self, pin: &Pin, npmrc_path: &Path, backup: bool) -> Result<(), NrsError> {
+        let names = pin.registry.iter().chain(pin.scopes.values());
+        for name in names.clone() {
+            let registry = self
+                .get(name)
+                .ok_or_else(|| NrsError::RegistryNotFound(name.to_string()))?;
+            if registry.disabled {
+                return Err(NrsError::RegistryDisabled(name.to_string()));
+            }
+        }
+
+        if backup && npmrc_path.exists() {
+            fs::copy(npmrc_path, npmrc_path.with_extension("npmrc.bak"))?;
+        }
+
+        let mut changes = Vec::new();
+        if let Some(name) = &pin.registry {
+            changes.push(("registry".to_string(), self.get_url(name).cloned()));
+        }
+        for (scope, name) in &pin.scopes {
+            changes.push((format!("{}:registry", scope), self.get_url(name).cloned()));
+        }
+        changes
+            .extend(self.auth_changes_for(|name| names.clone().any(|pinned| pinned == name), true));
+        write_npmrc_keys(npmrc_path, &changes)
+    }
+
+    /// Puts presets back as shipped, dropping the user's overrides and removals.
+    /// With no names, every preset of the enabled packs is reset. Returns the
+    /// reset names, and those skipped because a user registry now has the name.
+    pub fn reset_presets(
+        &mut self,
+        names: &[String],
+    ) -> Result<(Vec<String>, Vec<String>), NrsError> {
+        let mut reset = Vec::new();
+        let mut skipped = Vec::new();
+        let wanted: Vec<&str> = if names.is_empty() {
+            presets::in_packs(&self.packs)
+                .map(|preset| preset.name)
+                .collect()
+        } else {
+            names.iter().map(String::as_str).collect()
+        };
+        for name in wanted {
+            let Some(preset) = presets::find(name) else {
+                return Err(NrsError::PresetNotFound(name.to_string()));
+            };
+            if !self.packs.iter().any(|pack| pack == preset.pack) {
+                return Err(NrsError::PackDisabled(preset.pack.to_string()));
+            }
+            match self.get(name) {
+                Some(registry) if registry.origin != Origin::Builtin => {
+                    skipped.push(name.to_string());
+                    continue;
                 }
+                Some(registry) if *registry == preset.registry() => continue,
+                None if !self.removed_presets.iter().any(|removed| removed == name) => continue,
+                _ => {}
             }
+            self.registries
+                .retain(|registry| !(registry.origin == Origin::Builtin && registry.name == name));
+            self.removed_presets.retain(|removed| removed != name);
+            reset.push(name.to_string());
         }
-        lines.push(format!("registry={}", new_registry));
-        let content = lines.join("\n") + "\n";
-        fs::write(&npmrc_path, content)?;
-        Ok(())
+        if !reset.is_empty() {
+            self.merge_presets();
+            self.save()?;
+        }
+        Ok((reset, skipped))
     }
 
-    pub fn test_registry(&self, name: &str) -> Result<(bool, Option<u128>), NrsError> {
-        let url = self
-            .registries
-            .get(name)
-            .or_else(|| self.custom_registries.get(name))
-            .ok_or_else(|| NrsError::RegistryNotFound(name.to_string()))?;
-        let client = reqwest::blocking::Client::new();
-        let start = Instant::now();
-        let response = client.head(url).send();
-        let duration = start.elapsed().as_millis();
-        match response {
-            Ok(resp) if resp.status().is_success() => Ok((true, Some(duration))),
-            _ => Ok((false, Some(duration))),
+    /// Lists the presets of `pack`, bringing back any the user had removed.
+    /// Returns the pack's presets now listed as such.
+    pub fn enable_pack(&mut self, pack: &str) -> Result<Vec<String>, NrsError> {
+        if presets::find_pack(pack).is_none() {
+            return Err(NrsError::PackNotFound(pack.to_string()));
+        }
+        if self.packs.iter().any(|enabled| enabled == pack) {
+            return Ok(Vec::new());
+        }
+        self.packs.push(pack.to_string());
+        let names: Vec<&str> = presets::in_packs(&self.packs)
+            .filter(|preset| preset.pack == pack)
+            .map(|preset| preset.name)
+            .collect();
+        self.removed_presets
+            .retain(|removed| !names.contains(&removed.as_str()));
+        // Presets kept as custom registries by `disable_pack` become presets again.
+        for registry in self.registries.iter_mut() {
+            if registry.origin == Origin::Custom
+                && presets::find(&registry.name)
+                    .is_some_and(|preset| preset.pack == pack && preset.url == registry.url)
+            {
+                registry.origin = Origin::Builtin;
+            }
         }
+        self.merge_presets();
+        self.save()?;
+        Ok(names
+            .into_iter()
+            .filter(|name| {
+                self.get(name)
+                    .is_some_and(|registry| registry.origin == Origin::Builtin)
+            })
+            .map(str::to_string)
+            .collect())
+    }
+
+    /// Stops listing the presets of `pack`. Presets the user changed, selected,
+    /// mapped a scope to or stored a token for are kept as custom registries.
+    /// Returns the dropped and the kept names.
+    pub fn disable_pack(&mut self, pack: &str) -> Result<(Vec<String>, Vec<String>), NrsError> {
+        if presets::find_pack(pack).is_none() {
+            return Err(NrsError::PackNotFound(pack.to_string()));
+        }
+        let mut dropped = Vec::new();
+        let mut kept = Vec::new();
+        if !self.packs.iter().any(|enabled| enabled == pack) {
+            return Ok((dropped, kept));
+        }
+        for preset in presets::PRESETS.iter().filter(|preset| preset.pack == pack) {
+            let Some(registry) = self.get(preset.name) else {
+                continue;
+            };
+            if registry.origin != Origin::Builtin {
+                continue;
+            }
+            let in_use = self.current.as_deref() == Some(preset.name)
+                || self.scopes.values().any(|registry| registry == preset.name)
+                || self.auth.contains_key(preset.name);
+            if presets::is_pristine(registry) && !in_use {
+                dropped.push(preset.name.to_string());
+            } else {
+                kept.push(preset.name.to_string());
+            }
+        }
+        self.packs.retain(|enabled| enabled != pack);
+        self.removed_presets
+            .retain(|removed| presets::find(removed).is_none_or(|preset| preset.pack != pack));
+        self.registries
+            .retain(|registry| !dropped.contains(&registry.name));
+        for registry in self.registries.iter_mut() {
+            if kept.contains(&registry.name) {
+                registry.origin = Origin::Custom;
+            }
+        }
+        self.merge_presets();
+        self.save()?;
+        Ok((dropped, kept))
+    }
+
+    /// Probe target for a registry, honouring its timeout override.
+    pub fn probe_target(&self, name: &str, options: &ProbeOptions) -> Option<ProbeTarget> {
+        let registry = self.get(name)?;
+        Some(ProbeTarget {
+            name: Some(name.to_string()),
+            url: registry.url.clone(),
+            timeout: registry
+                .timeout_ms
+                .map(Duration::from_millis)
+                .unwrap_or(options.timeout),
+            strategy: registry.probe.clone(),
+        })
+    }
+
+    /// Probe target for a URL read from an `.npmrc`, which may not be a known registry.
+    pub fn probe_target_for_url(&self, url: &str, options: &ProbeOptions) -> ProbeTarget {
+        self.name_for_url(url)
+            .and_then(|name| self.probe_target(name, options))
+            .unwrap_or_else(|| ProbeTarget {
+                name: None,
+                url: url.to_string(),
+                timeout: options.timeout,
+                strategy: ProbeStrategy::default(),
+            })
+    }
+}
+
+pub fn normalize_scope(scope: &str) -> Result<String, NrsError> {
+    let scope = scope.trim().trim_end_matches(':');
+    let scope = if scope.starts_with('@') {
+        scope.to_string()
+    } else {
+        format!("@{}", scope)
+    };
+    if scope.len() < 2 || scope.contains(['/', ':', '=', ' ']) {
+        return Err(NrsError::InvalidScope(scope));
+    }
+    Ok(scope)
+}
+
+fn write_npmrc_keys(
+    npmrc_path: &Path,
+    changes: &[(String, Option<String>)],
+) -> Result<(), NrsError> {
+    if changes.is_empty() {
+        return Ok(());
+    }
+    let mut npmrc = Npmrc::load(npmrc_path)?;
+    for (key, value) in changes {
+        match value {
+            Some(value) => npmrc.set(key, value),
+            None => {
+                npmrc.remove(key);
+            }
+        }
+    }
+    let literal_token = changes.iter().any(|(key, value)| {
+        key.ends_with(":_authToken")
+            && value
+                .as_deref()
+                .is_some_and(|value| !value.starts_with("${"))
+    });
+    if literal_token || npmrc_path == get_npmrc_path()? {
+        npmrc.save_private(npmrc_path)
+    } else {
+        npmrc.save(npmrc_path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::effective::resolve_registry;
+
+    /// Points `HOME` at an empty directory, so `.nrsrc` and `.npmrc` are
+    /// scratch files, and drops npm settings inherited from the environment.
+    fn scratch_home(name: &str) -> PathBuf {
+        let home = env::temp_dir().join(format!("nrs-test-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&home);
+        fs::create_dir_all(&home).unwrap();
+        // SAFETY: no other test reads or writes the environment.
+        unsafe {
+            env::set_var("HOME", &home);
+            for (var, _) in env::vars_os() {
+                if var
+                    .to_str()
+                    .is_some_and(|var| var.to_ascii_lowercase().starts_with("npm_config_"))
+                {
+                    env::remove_var(var);
+                }
+            }
+        }
+        home
+    }
+
+    #[test]
+    fn restore_brings_back_the_npmrc_lines_prune_removed() {
+        let home = scratch_home("restore");
+        let url = "https://npm.corp.example/";
+        let mut config = Config::new();
+        config.add_registry("corp", url).unwrap();
+        config
+            .set_scope("@acme", "corp", NpmrcTarget::User)
+            .unwrap();
+        config
+            .set_auth("corp", AuthToken::Token("secret".to_string()))
+            .unwrap();
+        assert_eq!(resolve_registry(Some("@acme/ui")).unwrap().url, url);
+
+        // What prune does with a registry that kept failing.
+        config.trash_registry("corp").unwrap();
+        assert_ne!(resolve_registry(Some("@acme/ui")).unwrap().url, url);
+
+        let mut config = Config::load().unwrap();
+        config.restore_registry("corp").unwrap();
+        assert_eq!(config.scopes_for("corp"), ["@acme"]);
+        assert_eq!(resolve_registry(Some("@acme/ui")).unwrap().url, url);
+        let npmrc = Npmrc::load(&home.join(".npmrc")).unwrap();
+        assert_eq!(npmrc.get(&auth_key(url)), Some("secret"));
+
+        fs::remove_dir_all(home).unwrap();
     }
 }
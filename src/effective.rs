@@ -0,0 +1,84 @@
+use std::{fmt, path::PathBuf};
+
+use crate::{
+    error::NrsError,
+    npmrc::Npmrc,
+    utils::{NpmrcTarget, get_npmrc_path_for, npm_config_var},
+};
+
+pub const DEFAULT_REGISTRY: &str = "https://registry.npmjs.org/";
+
+/// Where npm picked a config value up from.
+pub enum Source {
+    Env(String),
+    File(NpmrcTarget, PathBuf),
+    Default,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Env(var) => write!(f, "{} (environment)", var),
+            Source::File(target, path) => write!(f, "{} ({})", path.display(), target.label()),
+            Source::Default => write!(f, "npm default"),
+        }
+    }
+}
+
+pub struct Resolved {
+    pub key: String,
+    pub url: String,
+    pub source: Source,
+}
+
+/// Extracts `@scope` from a package name such as `@acme/ui`.
+pub fn package_scope(package: &str) -> Option<&str> {
+    package
+        .starts_with('@')
+        .then(|| package.split('/').next().unwrap_or(package))
+        .filter(|scope| scope.len() > 1)
+}
+
+/// Resolves a single config key through npm's layers, highest precedence first:
+/// environment, project `.npmrc`, user `.npmrc`, global `npmrc`.
+pub fn resolve_key(key: &str) -> Result<Option<(String, Source)>, NrsError> {
+    if let Some((var, value)) = npm_config_var(key) {
+        return Ok(Some((value, Source::Env(var))));
+    }
+
+    let mut seen = Vec::new();
+    for target in [NpmrcTarget::Project, NpmrcTarget::User, NpmrcTarget::Global] {
+        let path = get_npmrc_path_for(target)?;
+        // Running from $HOME makes the project and user files the same one.
+        if seen.contains(&path) {
+            continue;
+        }
+        let npmrc = Npmrc::load(&path)?;
+        if let Some(value) = npmrc.get(key).filter(|value| !value.is_empty()) {
+            return Ok(Some((value.to_string(), Source::File(target, path))));
+        }
+        seen.push(path);
+    }
+    Ok(None)
+}
+
+/// The registry npm uses for `package` (or for unscoped installs when `None`).
+/// A scoped key anywhere wins over a plain `registry` in any layer.
+pub fn resolve_registry(package: Option<&str>) -> Result<Resolved, NrsError> {
+    if let Some(scope) = package.and_then(package_scope) {
+        let key = format!("{}:registry", scope);
+        if let Some((url, source)) = resolve_key(&key)? {
+            return Ok(Resolved { key, url, source });
+        }
+    }
+
+    let key = "registry".to_string();
+    Ok(match resolve_key(&key)? {
+        Some((url, source)) => Resolved { key, url, source },
+        None => Resolved {
+            key,
+            url: DEFAULT_REGISTRY.to_string(),
+            source: Source::Default,
+        },
+    })
+}
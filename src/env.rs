@@ -0,0 +1,243 @@
+use std::env;
+
+use clap::ValueEnum;
+
+use crate::{
+    config::{AuthToken, Config},
+    error::NrsError,
+    utils::auth_key,
+};
+
+/// Environment variable npm reads its default registry from.
+pub const NPM_REGISTRY: &str = "NPM_CONFIG_REGISTRY";
+
+/// Variables npm, Yarn classic, Yarn Berry and Bun read their default
+/// registry from.
+pub const REGISTRY_VARS: [&str; 4] = [
+    NPM_REGISTRY,
+    "YARN_REGISTRY",
+    "YARN_NPM_REGISTRY_SERVER",
+    "BUN_CONFIG_REGISTRY",
+];
+
+/// Variables Yarn Berry and Bun read the default registry's token from.
+const TOKEN_VARS: [&str; 2] = ["YARN_NPM_AUTH_TOKEN", "BUN_CONFIG_TOKEN"];
+
+/// Shells `nrs hook` can integrate with.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl Shell {
+    /// A statement that sets and exports `name` for this shell session.
+    pub fn export(self, name: &str, value: &str) -> String {
+        match self {
+            Shell::Bash | Shell::Zsh => format!("export {}={};", name, posix_quote(value)),
+            Shell::Fish => format!("set -gx {} {};", name, self.quote(value)),
+        }
+    }
+
+    /// A statement that removes `name` from this shell session.
+    pub fn unset(self, name: &str) -> String {
+        match self {
+            Shell::Bash | Shell::Zsh => format!("unset {};", name),
+            Shell::Fish => format!("set -e {};", name),
+        }
+    }
+
+    /// Quotes `value` as a single literal word.
+    pub fn quote(self, value: &str) -> String {
+        match self {
+            Shell::Bash | Shell::Zsh => posix_quote(value),
+            Shell::Fish => format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'")),
+        }
+    }
+}
+
+/// Output formats of `nrs env`.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum EnvFormat {
+    /// `export NAME='value'` for sh, bash and zsh
+    Posix,
+    /// `set -gx NAME 'value'`
+    Fish,
+    /// `$env:NAME = 'value'`
+    Powershell,
+    /// `NAME='value'` lines for a .env file
+    Dotenv,
+    /// `NAME=value` lines to append to $GITHUB_ENV
+    Github,
+    /// `NAME=value` lines for a GitLab dotenv report artifact
+    Gitlab,
+}
+
+/// The value of a variable from [`registry_vars`].
+pub enum EnvValue {
+    /// A registry URL.
+    Url(String),
+    /// A stored token, literal or kept as a `$VAR` reference.
+    Token(AuthToken),
+}
+
+impl EnvValue {
+    /// The value to hand to a child process, or `None` when the token's
+    /// variable is unset.
+    pub fn resolve(&self) -> Option<String> {
+        match self {
+            EnvValue::Url(url) => Some(url.clone()),
+            EnvValue::Token(AuthToken::Token(token)) => Some(token.clone()),
+            EnvValue::Token(AuthToken::Env(var)) => env::var(var).ok(),
+        }
+    }
+}
+
+impl EnvFormat {
+    /// The line that sets `name`, or `None` when the format cannot carry
+    /// it, as with scoped npm variables in a shell. Tokens kept as `$VAR`
+    /// references stay references wherever the format expands variables.
+    pub fn line(self, name: &str, value: &EnvValue) -> Option<String> {
+        match value {
+            EnvValue::Url(url) => self.literal(name, url),
+            // Report artifacts can be downloaded by anyone who can see the job.
+            EnvValue::Token(_) if self == EnvFormat::Gitlab => None,
+            EnvValue::Token(AuthToken::Token(token)) => self.literal(name, token),
+            EnvValue::Token(AuthToken::Env(var)) => self.reference(name, var),
+        }
+    }
+
+    /// A line that sets `name` to the value of `var` when it is evaluated.
+    fn reference(self, name: &str, var: &str) -> Option<String> {
+        if !is_shell_name(var) {
+            return None;
+        }
+        match self {
+            EnvFormat::Posix => {
+                is_shell_name(name).then(|| format!("export {}=\"${{{}}}\"", name, var))
+            }
+            EnvFormat::Fish => {
+                is_shell_name(name).then(|| format!("set -gx {} \"${}\"", name, var))
+            }
+            EnvFormat::Powershell if is_shell_name(name) => {
+                Some(format!("$env:{} = $env:{}", name, var))
+            }
+            EnvFormat::Powershell => Some(format!(
+                "[Environment]::SetEnvironmentVariable({}, $env:{})",
+                powershell_quote(name),
+                var
+            )),
+            EnvFormat::Dotenv => Some(format!("{}=\"${{{}}}\"", name, var)),
+            // $GITHUB_ENV values are taken as they are, so the variable is read now.
+            EnvFormat::Github => env::var(var)
+                .ok()
+                .and_then(|value| self.literal(name, &value)),
+            EnvFormat::Gitlab => None,
+        }
+    }
+
+    /// A line that sets `name` to `value` as it is.
+    fn literal(self, name: &str, value: &str) -> Option<String> {
+        match self {
+            EnvFormat::Posix => {
+                is_shell_name(name).then(|| format!("export {}={}", name, posix_quote(value)))
+            }
+            EnvFormat::Fish => is_shell_name(name)
+                .then(|| format!("set -gx {} {}", name, Shell::Fish.quote(value))),
+            EnvFormat::Powershell if is_shell_name(name) => {
+                Some(format!("$env:{} = {}", name, powershell_quote(value)))
+            }
+            EnvFormat::Powershell => Some(format!(
+                "[Environment]::SetEnvironmentVariable({}, {})",
+                powershell_quote(name),
+                powershell_quote(value)
+            )),
+            EnvFormat::Dotenv if !value.contains(['\'', '\n']) => {
+                Some(format!("{}='{}'", name, value))
+            }
+            EnvFormat::Dotenv => Some(format!(
+                "{}=\"{}\"",
+                name,
+                value
+                    .replace('\\', "\\\\")
+                    .replace('"', "\\\"")
+                    .replace('\n', "\\n")
+            )),
+            // Multi-line values need GitHub's heredoc syntax.
+            EnvFormat::Github if value.contains('\n') => {
+                Some(format!("{}<<NRS_EOF\n{}\nNRS_EOF", name, value))
+            }
+            EnvFormat::Github => Some(format!("{}={}", name, value)),
+            // GitLab only accepts letters, digits and underscores in keys and
+            // single-line values.
+            EnvFormat::Gitlab => (is_shell_name(name) && !value.contains('\n'))
+                .then(|| format!("{}={}", name, value)),
+        }
+    }
+}
+
+/// Whether `name` is a portable variable name: letters, digits and
+/// underscores, not starting with a digit.
+fn is_shell_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// The variables that select registry `name` without an `.npmrc`: the
+/// default registry for each package manager, npm's `@scope:registry` keys
+/// for the configured scopes, and the stored tokens those registries need.
+pub fn registry_vars(config: &Config, name: &str) -> Result<Vec<(String, EnvValue)>, NrsError> {
+    let registry = config
+        .get(name)
+        .ok_or_else(|| NrsError::RegistryNotFound(name.to_string()))?;
+    if registry.disabled {
+        return Err(NrsError::RegistryDisabled(name.to_string()));
+    }
+    let mut vars: Vec<(String, EnvValue)> = REGISTRY_VARS
+        .iter()
+        .map(|var| (var.to_string(), EnvValue::Url(registry.url.clone())))
+        .collect();
+
+    let mut scopes: Vec<_> = config.scopes.iter().collect();
+    scopes.sort();
+    for (scope, target) in &scopes {
+        if let Some(url) = config.get_url(target) {
+            vars.push((
+                format!("npm_config_{}:registry", scope),
+                EnvValue::Url(url.clone()),
+            ));
+        }
+    }
+
+    let mut active: Vec<&str> = scopes.iter().map(|(_, target)| target.as_str()).collect();
+    active.push(name);
+    active.sort();
+    active.dedup();
+    for target in active {
+        let (Some(token), Some(url)) = (config.auth.get(target), config.get_url(target)) else {
+            continue;
+        };
+        if target == name {
+            vars.extend(
+                TOKEN_VARS
+                    .iter()
+                    .map(|var| (var.to_string(), EnvValue::Token(token.clone()))),
+            );
+        }
+        vars.push((
+            format!("npm_config_{}", auth_key(url)),
+            EnvValue::Token(token.clone()),
+        ));
+    }
+    Ok(vars)
+}
+
+fn posix_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+fn powershell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
@@ -1,4 +1,4 @@
-use std::io;
+use std::{io, path::PathBuf};
 
 use thiserror::Error;
 
@@ -18,4 +18,54 @@ pub enum NrsError {
     HomeDirNotFound,
     #[error("Invalid registry URL: {0}")]
     InvalidRegistryUrl(String),
+    #[error("Invalid scope: {0}")]
+    InvalidScope(String),
+    #[error("Scope not found: {0}")]
+    ScopeNotFound(String),
+    #[error("No healthy registry among: {0}")]
+    NoHealthyRegistry(String),
+    #[error("No enabled registry is tagged {0}")]
+    NoTaggedRegistry(String),
+    #[error("Invalid duration: {0}")]
+    InvalidDuration(String),
+    #[error("No credentials stored for registry: {0}")]
+    AuthNotFound(String),
+    #[error("No pruned registry named {0} in the trash")]
+    NotInTrash(String),
+    #[error("Registry already exists: {0}")]
+    RegistryExists(String),
+    #[error("Registry is disabled: {0} (re-enable it with nrs edit {0} --enable)")]
+    RegistryDisabled(String),
+    #[error("No built-in preset named {0}")]
+    PresetNotFound(String),
+    #[error("No preset pack named {0}")]
+    PackNotFound(String),
+    #[error("Preset pack {0} is disabled (enable it with nrs presets enable {0})")]
+    PackDisabled(String),
+    #[error("Nothing to import: {} not found", .0.display())]
+    ImportSourceNotFound(PathBuf),
+    #[error("Invalid registry set: {0}")]
+    InvalidRegistrySet(String),
+    #[error("Registry {0} is defined by the project; run nrs adopt {0} to make it yours first")]
+    ProjectRegistry(String),
+    #[error("Registry {0} is not defined by the project")]
+    NotProjectRegistry(String),
+    #[error("No .nrs or .nrsrc.json in {} or its parents (create one with nrs init)", .0.display())]
+    PinNotFound(PathBuf),
+    #[error("Invalid pin file {}: {}", .0.display(), .1)]
+    InvalidPin(PathBuf, String),
+    #[error("Pin file already exists: {} (use --force to overwrite it)", .0.display())]
+    PinExists(PathBuf),
+    #[error("Nothing to pin: no current registry or scopes (pass --registry)")]
+    NothingToPin,
+    #[error("Could not run {0}: {1}")]
+    CommandFailed(String, io::Error),
+    #[error("Another nrs process is using the config (lock held on {})", .0.display())]
+    Locked(PathBuf),
+    #[error("~/.nrsrc has schema version {0}, which is newer than this nrs supports")]
+    UnsupportedConfigVersion(u32),
+    #[error("--format toml only applies to nrs export")]
+    TomlOnlyForExport,
+    #[error("Invalid ~/.nrsrc: {0}")]
+    InvalidConfig(String),
 }
@@ -0,0 +1,134 @@
+use std::{collections::HashMap, fs, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::NrsError,
+    output::ProbeReport,
+    probe::Health,
+    utils::{get_history_path, lock_config, unix_now, write_atomic},
+};
+
+/// How many results are kept per registry.
+const MAX_RECORDS: usize = 50;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ProbeRecord {
+    /// Unix timestamp, in seconds.
+    pub timestamp: u64,
+    pub url: String,
+    pub health: Health,
+    pub latency_ms: u128,
+}
+
+impl ProbeRecord {
+    pub fn age(&self) -> Duration {
+        Duration::from_secs(unix_now().saturating_sub(self.timestamp))
+    }
+}
+
+/// Probe results from previous runs, stored next to `.nrsrc`.
+#[derive(Default, Serialize, Deserialize)]
+pub struct History {
+    #[serde(default)]
+    pub registries: HashMap<String, Vec<ProbeRecord>>,
+}
+
+impl History {
+    /// Loads the history; a missing or unreadable file is treated as empty,
+    /// since it is only a cache.
+    pub fn load() -> Result<Self, NrsError> {
+        let path = get_history_path()?;
+        if !path.exists() {
+            return Ok(History::default());
+        }
+        let contents = fs::read_to_string(&path)?;
+        Ok(serde_json::from_str(&contents).unwrap_or_default())
+    }
+
+    /// Records fresh probes under the config lock, re-reading the file first
+    /// so results saved by a parallel run are kept. Returns the updated history.
+    pub fn append(reports: &[ProbeReport]) -> Result<Self, NrsError> {
+        let _lock = lock_config()?;
+        let mut history = History::load()?;
+        for report in reports {
+            history.record(report);
+        }
+        history.save()?;
+        Ok(history)
+    }
+
+    pub fn save(&self) -> Result<(), NrsError> {
+        let contents = serde_json::to_string_pretty(self)?;
+        write_atomic(&get_history_path()?, &contents)?;
+        Ok(())
+    }
+
+    /// Appends a fresh probe of a named registry; cached and anonymous reports are ignored.
+    pub fn record(&mut self, report: &ProbeReport) {
+        if let Some(name) = &report.name {
+            self.record_as(name, report);
+        }
+    }
+
+    /// Appends a fresh probe under `key`, e.g. the URL of a registry nrs doesn't know.
+    pub fn record_as(&mut self, key: &str, report: &ProbeReport) {
+        let Some(latency_ms) = report.latency_ms else {
+            return;
+        };
+        if report.cached {
+            return;
+        }
+        let records = self.registries.entry(key.to_string()).or_default();
+        records.push(ProbeRecord {
+            timestamp: unix_now(),
+            url: report.url.clone(),
+            health: report.health,
+            latency_ms,
+        });
+        if records.len() > MAX_RECORDS {
+            records.drain(..records.len() - MAX_RECORDS);
+        }
+    }
+
+    pub fn forget(&mut self, name: &str) {
+        self.registries.remove(name);
+    }
+
+    pub fn last(&self, name: &str) -> Option<&ProbeRecord> {
+        self.registries.get(name)?.last()
+    }
+
+    /// Number of most recent probes of `url`, within `window`, that found the
+    /// registry down without a healthy or auth-required answer in between.
+    pub fn consecutive_failures(&self, name: &str, url: &str, window: Duration) -> usize {
+        self.registries
+            .get(name)
+            .map(|records| {
+                records
+                    .iter()
+                    .rev()
+                    .take_while(|record| record.url == url && record.age() <= window)
+                    .take_while(|record| record.health.is_down())
+                    .count()
+            })
+            .unwrap_or_default()
+    }
+
+    /// The latest result for `name`, if it was taken against `url` within `max_age`.
+    pub fn fresh(&self, name: &str, url: &str, max_age: Duration) -> Option<&ProbeRecord> {
+        self.last(name)
+            .filter(|record| record.url == url && record.age() <= max_age)
+    }
+}
+
+/// Short relative age, e.g. `42s`, `5m`, `3h`, `2d`.
+pub fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    match secs {
+        0..60 => format!("{}s", secs),
+        60..3600 => format!("{}m", secs / 60),
+        3600..86400 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86400),
+    }
+}
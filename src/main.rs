@@ -1,6 +1,17 @@
 mod commands;
 mod config;
+mod effective;
+mod env;
 mod error;
+mod history;
+mod migrate;
+mod npmrc;
+mod output;
+mod pin;
+mod presets;
+mod probe;
+mod registry;
+mod share;
 mod utils;
 
 use clap::{
@@ -9,19 +20,37 @@ use clap::{
 };
 use clap_autocomplete::{add_subcommand, test_subcommand};
 use commands::{
-    Commands, add, current, doctor, edit, list, prune, remove, reset, show, test, use_cmd,
+    Commands, Race, add, adopt, apply, auth, config_cmd, current, doctor, edit, effective, env_cmd,
+    exec, export, hook, hook_env, import, init, list, presets, prune, remove, reset, restore,
+    scope, show, sync, test, use_cmd, use_fastest,
 };
 use config::Config;
 use error::NrsError;
+use output::{OutputFormat, print_error};
+use probe::ProbeOptions;
+use std::time::Duration;
+use utils::{NpmrcTarget, lock_config, parse_duration};
 
 #[derive(Parser)]
 #[command(name = "nrs", about = "Node.js registry switcher")]
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+    /// Output format
+    #[arg(long, global = true, value_enum, default_value = "table")]
+    format: OutputFormat,
+    /// Shorthand for --format json
+    #[arg(long, global = true)]
+    json: bool,
+    /// Default timeout for registry probes, e.g. 500ms, 5s
+    #[arg(long, global = true, default_value = "10s", value_parser = parse_duration)]
+    timeout: Duration,
+    /// Number of registries probed concurrently
+    #[arg(long, global = true, default_value_t = 8)]
+    jobs: usize,
 }
 
-fn main() -> Result<(), NrsError> {
+fn main() {
     let styles = Styles::styled()
         .header(AnsiColor::Yellow.on_default() | Effects::BOLD)
         .usage(AnsiColor::Green.on_default() | Effects::BOLD)
@@ -47,24 +76,157 @@ fn main() -> Result<(), NrsError> {
         }
     }
 
-    let cli = Cli::from_arg_matches(&matches)?;
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    let format = if cli.json {
+        OutputFormat::Json
+    } else {
+        cli.format
+    };
+    if format != OutputFormat::Table {
+        colored::control::set_override(false);
+    }
+    if let Err(err) = run(cli, format) {
+        print_error(&err, format);
+        std::process::exit(1);
+    }
+}
+
+fn run(cli: Cli, format: OutputFormat) -> Result<(), NrsError> {
+    let probe_options = ProbeOptions {
+        timeout: cli.timeout,
+        jobs: cli.jobs,
+    };
+    if format == OutputFormat::Toml && !matches!(cli.command, Commands::Export { .. }) {
+        return Err(NrsError::TomlOnlyForExport);
+    }
+    // These only read the config and run on every directory change (the shell
+    // hook) or for as long as the child command (exec), so they skip the lock.
+    match cli.command {
+        Commands::Hook { shell } => return hook(shell),
+        Commands::HookEnv { shell } => return hook_env(shell),
+        Commands::Exec { name, command } => return exec(Config::load()?, &name, &command),
+        _ => {}
+    }
+    // Held until the command finishes, covering the whole load-modify-save cycle.
+    // Commands that probe registries take it themselves once the probes are done.
+    let probes = matches!(
+        cli.command,
+        Commands::Test { .. } | Commands::Prune { .. } | Commands::Use { fastest: true, .. }
+    );
+    let _lock = if probes { None } else { Some(lock_config()?) };
     let config = Config::load()?;
 
     match cli.command {
-        Commands::Ls { sort } => list(config, sort),
+        Commands::Ls { sort, long } => list(config, sort, long, format),
         Commands::Use {
             name,
+            fastest,
+            among,
+            tag,
+            samples,
             backup,
             local,
-        } => use_cmd(config, &name, backup, local),
-        Commands::Add { name, url } => add(config, &name, &url),
-        Commands::Remove { name } => remove(config, &name),
-        Commands::Reset { yes, all } => reset(config, yes, all),
-        Commands::Prune { local, dry_run } => prune(config, local, dry_run),
-        Commands::Doctor => doctor(config),
-        Commands::Edit { name, new_url } => edit(config, &name, &new_url),
-        Commands::Show { local } => show(local),
-        Commands::Current { local } => current(config, local),
-        Commands::Test { name, local } => test(config, &name, local),
+            global_npmrc,
+        } => {
+            let target = NpmrcTarget::from_flags(local, global_npmrc);
+            match name {
+                Some(name) => use_cmd(config, &name, backup, target, format),
+                None if !fastest => apply(config, backup, format),
+                None => use_fastest(
+                    config,
+                    Race {
+                        among: &among,
+                        tag: tag.as_deref(),
+                        samples: samples.into(),
+                    },
+                    backup,
+                    target,
+                    probe_options,
+                    format,
+                ),
+            }
+        }
+        Commands::Add { name, url } => add(config, &name, &url, format),
+        Commands::Adopt { name } => adopt(config, &name, format),
+        Commands::Remove { name } => remove(config, &name, format),
+        Commands::Reset { yes, all } => reset(config, yes, all, format),
+        Commands::Prune {
+            local,
+            dry_run,
+            min_failures,
+            window,
+        } => prune(
+            config,
+            local,
+            dry_run,
+            min_failures.into(),
+            window,
+            probe_options,
+            format,
+        ),
+        Commands::Restore { name } => restore(config, name.as_deref(), format),
+        Commands::Doctor => doctor(config, format),
+        Commands::Edit(args) => edit(config, args, format),
+        Commands::Show {
+            local,
+            global_npmrc,
+        } => show(NpmrcTarget::from_flags(local, global_npmrc), format),
+        Commands::Current {
+            local,
+            global_npmrc,
+        } => current(config, NpmrcTarget::from_flags(local, global_npmrc), format),
+        Commands::Test {
+            name,
+            local,
+            samples,
+            cached,
+            max_age,
+        } => test(
+            config,
+            &name,
+            local,
+            samples.into(),
+            cached.then_some(max_age),
+            probe_options,
+            format,
+        ),
+        Commands::Scope { action } => scope(config, action, format),
+        Commands::Sync {
+            adopt,
+            apply,
+            local,
+            global_npmrc,
+        } => sync(
+            config,
+            adopt,
+            apply,
+            NpmrcTarget::from_flags(local, global_npmrc),
+            format,
+        ),
+        Commands::Config { action } => config_cmd(config, action, format),
+        Commands::Init {
+            registry,
+            file,
+            define,
+            force,
+        } => init(config, registry, &file, define, force, format),
+        Commands::Apply { backup } => apply(config, backup, format),
+        Commands::Export { names } => export(config, &names, format),
+        Commands::Import {
+            source,
+            file,
+            strategy,
+        } => import(config, &source, file, strategy, format),
+        Commands::Hook { .. } | Commands::HookEnv { .. } | Commands::Exec { .. } => {
+            unreachable!("handled before locking")
+        }
+        Commands::Env {
+            name,
+            env_format,
+            show_secrets,
+        } => env_cmd(config, &name, env_format, show_secrets, format),
+        Commands::Presets { action } => presets(config, action, format),
+        Commands::Auth { action } => auth(config, action, format),
+        Commands::Effective { package } => effective(config, package.as_deref(), format),
     }
 }
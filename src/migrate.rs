@@ -0,0 +1,392 @@
+use std::{fs, path::PathBuf};
+
+use serde_json::{Map, Value, json};
+
+use crate::{
+    error::NrsError,
+    presets,
+    utils::{get_config_path, write_private},
+};
+
+/// Schema version written by this build of nrs.
+pub const CURRENT_VERSION: u32 = 3;
+
+/// `MIGRATIONS[n]` upgrades a version `n` document to version `n + 1`.
+const MIGRATIONS: [fn(&mut Map<String, Value>); CURRENT_VERSION as usize] =
+    [v0_to_v1, v1_to_v2, v2_to_v3];
+
+/// Version of a raw `.nrsrc` document; files written before versioning have none.
+pub fn version_of(value: &Value) -> u32 {
+    value
+        .get("version")
+        .and_then(Value::as_u64)
+        .map_or(0, |version| version as u32)
+}
+
+/// Upgrades a raw `.nrsrc` document to [`CURRENT_VERSION`] in memory.
+pub fn migrate(mut value: Value) -> Result<Value, NrsError> {
+    let version = version_of(&value);
+    if version > CURRENT_VERSION {
+        return Err(NrsError::UnsupportedConfigVersion(version));
+    }
+    let Some(document) = value.as_object_mut() else {
+        return Err(NrsError::InvalidConfig(
+            "expected a JSON object".to_string(),
+        ));
+    };
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(document);
+    }
+    document.insert("version".to_string(), json!(CURRENT_VERSION));
+    Ok(value)
+}
+
+/// Where the pre-migration copy of a version `version` file is kept.
+pub fn backup_path(version: u32) -> Result<PathBuf, NrsError> {
+    Ok(get_config_path()?.with_file_name(format!(".nrsrc.v{}.bak", version)))
+}
+
+/// Copies `.nrsrc` aside before it is first rewritten in a newer schema.
+/// Returns the backup path, or `None` when the file is already current.
+pub fn backup_if_outdated() -> Result<Option<PathBuf>, NrsError> {
+    let config_path = get_config_path()?;
+    let Ok(contents) = fs::read_to_string(&config_path) else {
+        return Ok(None);
+    };
+    let version = serde_json::from_str::<Value>(&contents)
+        .map(|value| version_of(&value))
+        .unwrap_or_default();
+    if version >= CURRENT_VERSION {
+        return Ok(None);
+    }
+    let backup = backup_path(version)?;
+    // The old file may hold literal tokens.
+    write_private(&backup, &contents)?;
+    Ok(Some(backup))
+}
+
+/// The original layout: `registries`, `custom_registries`, `registry_order`
+/// and `current`, with later additions as optional keys. Fills in anything
+/// that hand-edited or very old files may lack.
+fn v0_to_v1(document: &mut Map<String, Value>) {
+    for key in ["registries", "custom_registries"] {
+        if !document.get(key).is_some_and(Value::is_object) {
+            document.insert(key.to_string(), json!({}));
+        }
+    }
+    if !document.get("registry_order").is_some_and(Value::is_array) {
+        let mut names: Vec<String> = ["registries", "custom_registries"]
+            .iter()
+            .filter_map(|key| document.get(*key)?.as_object())
+            .flat_map(|registries| registries.keys().cloned())
+            .collect();
+        names.sort();
+        document.insert("registry_order".to_string(), json!(names));
+    }
+    document.entry("current").or_insert(Value::Null);
+}
+
+/// Folds `registries`, `custom_registries`, `registry_order`, `timeouts`,
+/// `probes` and `keep` into a single ordered list of registry records, and
+/// gives trash entries the same shape.
+fn v1_to_v2(document: &mut Map<String, Value>) {
+    let mut take_object = |key: &str| match document.remove(key) {
+        Some(Value::Object(map)) => map,
+        _ => Map::new(),
+    };
+    let builtin = take_object("registries");
+    let custom = take_object("custom_registries");
+    let timeouts = take_object("timeouts");
+    let probes = take_object("probes");
+    let keep: Vec<String> = match document.remove("keep") {
+        Some(Value::Array(names)) => names
+            .into_iter()
+            .filter_map(|name| name.as_str().map(str::to_string))
+            .collect(),
+        _ => Vec::new(),
+    };
+    let mut order: Vec<String> = match document.remove("registry_order") {
+        Some(Value::Array(names)) => names
+            .into_iter()
+            .filter_map(|name| name.as_str().map(str::to_string))
+            .collect(),
+        _ => Vec::new(),
+    };
+    // Registries missing from the order go last, alphabetically.
+    let mut rest: Vec<String> = builtin
+        .keys()
+        .chain(custom.keys())
+        .filter(|name| !order.contains(name))
+        .cloned()
+        .collect();
+    rest.sort();
+    order.extend(rest);
+    let mut seen = Vec::new();
+    order.retain(|name| {
+        let first = !seen.contains(name);
+        seen.push(name.clone());
+        first
+    });
+
+    let registries: Vec<Value> = order
+        .iter()
+        .filter_map(|name| {
+            // Built-ins won lookups when a name was in both maps.
+            let (url, origin) = match (builtin.get(name), custom.get(name)) {
+                (Some(url), _) => (url, "builtin"),
+                (None, Some(url)) => (url, "custom"),
+                (None, None) => return None,
+            };
+            let mut record = json!({ "name": name, "url": url, "origin": origin });
+            if let Some(timeout) = timeouts.get(name) {
+                record["timeout_ms"] = timeout.clone();
+            }
+            if let Some(probe) = probes.get(name) {
+                record["probe"] = probe.clone();
+            }
+            if keep.contains(name) {
+                record["keep"] = json!(true);
+            }
+            Some(record)
+        })
+        .collect();
+    document.insert("registries".to_string(), json!(registries));
+
+    if let Some(Value::Array(trash)) = document.get_mut("trash") {
+        for entry in trash.iter_mut().filter_map(Value::as_object_mut) {
+            entry.entry("origin").or_insert_with(|| json!("custom"));
+            if let Some(timeout) = entry.remove("timeout")
+                && !timeout.is_null()
+            {
+                entry.insert("timeout_ms".to_string(), timeout);
+            }
+            if let Some(probe) = entry.remove("probe")
+                && !probe.is_null()
+            {
+                entry.insert("probe".to_string(), probe);
+            }
+        }
+    }
+}
+
+/// Stops storing presets: built-in records that match the shipped preset are
+/// dropped, overrides keep only what the user changed, and presets missing
+/// from the list are recorded in `removed_presets`. Built-ins that no longer
+/// ship (such as the old npmMirror/skimdb entry) are dropped unless they are
+/// selected or were customised, in which case they become custom registries.
+fn v2_to_v3(document: &mut Map<String, Value>) {
+    let current = document
+        .get("current")
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    let Some(Value::Array(registries)) = document.get_mut("registries") else {
+        document.insert("registries".to_string(), json!([]));
+        document.insert("removed_presets".to_string(), json!([]));
+        return;
+    };
+    let builtin_names: Vec<String> = registries
+        .iter()
+        .filter(|record| record["origin"] == "builtin")
+        .filter_map(|record| record["name"].as_str().map(str::to_string))
+        .collect();
+    registries.retain_mut(|record| {
+        let Some(record) = record.as_object_mut() else {
+            return false;
+        };
+        if record.get("origin").and_then(Value::as_str) != Some("builtin") {
+            return true;
+        }
+        let name = record
+            .get("name")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let bare = record.len() == 3;
+        match presets::find(name) {
+            Some(preset) => {
+                if bare && record.get("url").and_then(Value::as_str) == Some(preset.url) {
+                    return false;
+                }
+                record
+                    .entry("description")
+                    .or_insert_with(|| json!(preset.description));
+                record.entry("home").or_insert_with(|| json!(preset.home));
+                true
+            }
+            None => {
+                if bare && current.as_deref() != Some(name) {
+                    return false;
+                }
+                record.insert("origin".to_string(), json!("custom"));
+                true
+            }
+        }
+    });
+    // Only the presets a version 2 file could list; later ones are new, not removed.
+    let removed: Vec<&str> = ["npm", "yarn", "taobao", "tencent", "github"]
+        .into_iter()
+        .filter(|name| !builtin_names.iter().any(|builtin| builtin == name))
+        .collect();
+    document.insert("removed_presets".to_string(), json!(removed));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    /// A `.nrsrc` as the original release wrote it, with every preset stored.
+    fn v0(current: &str, custom: Value) -> Value {
+        json!({
+            "registries": {
+                "npm": "https://registry.npmjs.org/",
+                "yarn": "https://registry.yarnpkg.com/",
+                "taobao": "https://registry.npmmirror.com/",
+                "tencent": "https://mirrors.cloud.tencent.com/npm/",
+                "npmMirror": "https://skimdb.npmjs.com/registry/",
+                "github": "https://npm.pkg.github.com/"
+            },
+            "custom_registries": custom,
+            "registry_order": ["npm", "yarn", "taobao", "tencent", "npmMirror", "github", "corp"],
+            "current": current
+        })
+    }
+
+    fn builtin(name: &str, url: &str) -> Value {
+        json!({ "name": name, "url": url, "origin": "builtin" })
+    }
+
+    fn v2(current: &str, registries: Vec<Value>) -> Value {
+        json!({ "version": 2, "registries": registries, "current": current })
+    }
+
+    fn v2_defaults() -> Vec<Value> {
+        vec![
+            builtin("npm", "https://registry.npmjs.org/"),
+            builtin("yarn", "https://registry.yarnpkg.com/"),
+            builtin("taobao", "https://registry.npmmirror.com/"),
+            builtin("tencent", "https://mirrors.cloud.tencent.com/npm/"),
+            builtin("npmMirror", "https://skimdb.npmjs.com/registry/"),
+            builtin("github", "https://npm.pkg.github.com/"),
+        ]
+    }
+
+    fn names(value: &Value) -> Vec<&str> {
+        value["registries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|record| record["name"].as_str().unwrap())
+            .collect()
+    }
+
+    /// Loads a migrated document like `Config::load` and saves it again.
+    fn round_trip(migrated: &Value) -> Value {
+        let mut config: Config = serde_json::from_value(migrated.clone()).unwrap();
+        config.merge_presets();
+        serde_json::to_value(&config).unwrap()
+    }
+
+    #[test]
+    fn v0_to_v3_keeps_only_custom_registries() {
+        let migrated = migrate(v0("npm", json!({ "corp": "https://npm.corp.example/" }))).unwrap();
+        assert_eq!(migrated["version"], json!(CURRENT_VERSION));
+        assert_eq!(names(&migrated), ["corp"]);
+        assert_eq!(migrated["registries"][0]["origin"], "custom");
+        assert_eq!(
+            migrated["registries"][0]["url"],
+            "https://npm.corp.example/"
+        );
+        assert_eq!(migrated["current"], "npm");
+        assert_eq!(migrated["removed_presets"], json!([]));
+        for key in ["custom_registries", "registry_order"] {
+            assert!(migrated.get(key).is_none(), "{} left behind", key);
+        }
+    }
+
+    #[test]
+    fn v0_to_v3_round_trips() {
+        let migrated =
+            migrate(v0("taobao", json!({ "corp": "https://npm.corp.example/" }))).unwrap();
+        let saved = round_trip(&migrated);
+        assert_eq!(saved["current"], "taobao");
+        assert_eq!(names(&saved), ["corp"]);
+        assert_eq!(migrate(saved.clone()).unwrap(), saved);
+    }
+
+    #[test]
+    fn v2_to_v3_round_trips() {
+        let mut registries = v2_defaults();
+        registries.push(json!({
+            "name": "corp",
+            "url": "https://npm.corp.example/",
+            "origin": "custom",
+            "keep": true
+        }));
+        let migrated = migrate(v2("corp", registries)).unwrap();
+        assert_eq!(names(&migrated), ["corp"]);
+        assert_eq!(migrated["registries"][0]["keep"], json!(true));
+        let saved = round_trip(&migrated);
+        assert_eq!(names(&saved), ["corp"]);
+        assert_eq!(saved["current"], "corp");
+        assert_eq!(migrate(saved.clone()).unwrap(), saved);
+    }
+
+    #[test]
+    fn customised_preset_is_kept_as_an_override() {
+        let mut registries = v2_defaults();
+        registries[0]["timeout_ms"] = json!(30000);
+        registries[2]["url"] = json!("https://registry.npmmirror.example/");
+        let migrated = migrate(v2("npm", registries)).unwrap();
+        assert_eq!(names(&migrated), ["npm", "taobao"]);
+        let npm = &migrated["registries"][0];
+        assert_eq!(npm["origin"], "builtin");
+        assert_eq!(npm["timeout_ms"], json!(30000));
+        assert!(npm["description"].is_string());
+        assert!(npm["home"].is_string());
+        assert_eq!(
+            migrated["registries"][1]["url"],
+            "https://registry.npmmirror.example/"
+        );
+
+        let saved = round_trip(&migrated);
+        assert_eq!(names(&saved), ["npm", "taobao"]);
+        assert_eq!(saved["registries"][0]["timeout_ms"], json!(30000));
+    }
+
+    #[test]
+    fn unselected_npm_mirror_is_dropped() {
+        let migrated = migrate(v2("npm", v2_defaults())).unwrap();
+        assert!(!names(&migrated).contains(&"npmMirror"));
+    }
+
+    #[test]
+    fn selected_npm_mirror_becomes_custom() {
+        let migrated = migrate(v2("npmMirror", v2_defaults())).unwrap();
+        assert_eq!(names(&migrated), ["npmMirror"]);
+        assert_eq!(migrated["registries"][0]["origin"], "custom");
+        assert_eq!(migrated["current"], "npmMirror");
+
+        let config: Config = serde_json::from_value(migrated).unwrap();
+        assert_eq!(
+            config.get_url("npmMirror").map(String::as_str),
+            Some("https://skimdb.npmjs.com/registry/")
+        );
+    }
+
+    #[test]
+    fn missing_presets_are_recorded_as_removed() {
+        let registries: Vec<Value> = v2_defaults()
+            .into_iter()
+            .filter(|record| record["name"] != "yarn" && record["name"] != "github")
+            .collect();
+        let migrated = migrate(v2("npm", registries)).unwrap();
+        // Presets added after version 2, such as huawei, are not removals.
+        assert_eq!(migrated["removed_presets"], json!(["yarn", "github"]));
+
+        let mut config: Config = serde_json::from_value(migrated).unwrap();
+        config.merge_presets();
+        assert!(config.get("yarn").is_none());
+        assert!(config.get("github").is_none());
+        assert!(config.get("huawei").is_some());
+    }
+}
@@ -0,0 +1,382 @@
+use std::{fmt, fs, path::Path};
+
+use crate::{
+    error::NrsError,
+    utils::{write_atomic, write_private},
+};
+
+/// An ini-style `.npmrc` document.
+///
+/// Lines that are not touched are written back byte-for-byte, so comments,
+/// ordering, unknown keys and CRLF line endings survive a round trip.
+pub struct Npmrc {
+    lines: Vec<Line>,
+    newline: &'static str,
+}
+
+enum Line {
+    Entry {
+        section: Option<String>,
+        key: String,
+        value: String,
+        raw: Option<String>,
+    },
+    Section {
+        name: String,
+        raw: String,
+    },
+    Other(String),
+}
+
+impl Npmrc {
+    pub fn parse(contents: &str) -> Self {
+        let newline = if contents.contains("\r\n") {
+            "\r\n"
+        } else {
+            "\n"
+        };
+        let mut section = None;
+        let lines = contents
+            .lines()
+            .map(|raw| {
+                let line = parse_line(raw, section.as_deref());
+                if let Line::Section { name, .. } = &line {
+                    section = Some(name.clone());
+                }
+                line
+            })
+            .collect();
+        Npmrc { lines, newline }
+    }
+
+    /// Loads a file, treating a missing file as an empty document.
+    pub fn load(path: &Path) -> Result<Self, NrsError> {
+        if !path.exists() {
+            return Ok(Npmrc::parse(""));
+        }
+        Ok(Npmrc::parse(&fs::read_to_string(path)?))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), NrsError> {
+        write_atomic(path, &self.to_string())?;
+        Ok(())
+    }
+
+    /// Like [`Npmrc::save`], but a file that doesn't exist yet is created
+    /// readable only by its owner, as npm does for the user config. An
+    /// existing file keeps its permissions.
+    pub fn save_private(&self, path: &Path) -> Result<(), NrsError> {
+        if path.exists() {
+            return self.save(path);
+        }
+        write_private(path, &self.to_string())
+    }
+
+    /// Value of a top-level key; like npm, the last occurrence wins.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries()
+            .filter(|(k, _)| *k == key)
+            .map(|(_, v)| v)
+            .last()
+    }
+
+    /// Replaces the first occurrence of `key` in place and drops any duplicates.
+    /// New keys are appended after the last top-level line.
+    pub fn set(&mut self, key: &str, value: &str) {
+        let mut found = false;
+        self.lines.retain_mut(|line| match line {
+            Line::Entry {
+                section: None,
+                key: k,
+                value: v,
+                raw,
+            } if k == key => {
+                if found {
+                    return false;
+                }
+                found = true;
+                *v = value.to_string();
+                *raw = None;
+                true
+            }
+            _ => true,
+        });
+        if !found {
+            let index = self
+                .lines
+                .iter()
+                .position(|line| matches!(line, Line::Section { .. }))
+                .unwrap_or(self.lines.len());
+            self.lines.insert(
+                index,
+                Line::Entry {
+                    section: None,
+                    key: key.to_string(),
+                    value: value.to_string(),
+                    raw: None,
+                },
+            );
+        }
+    }
+
+    /// Removes every top-level occurrence of `key`, returning whether any existed.
+    pub fn remove(&mut self, key: &str) -> bool {
+        let before = self.lines.len();
+        self.lines
+            .retain(|line| !matches!(line, Line::Entry { section: None, key: k, .. } if k == key));
+        self.lines.len() != before
+    }
+
+    /// Replaces the value of every entry, in any section, that `replace`
+    /// returns a new value for, keeping the position of each line.
+    pub fn replace_values(&mut self, replace: impl Fn(&str, &str) -> Option<String>) {
+        for line in &mut self.lines {
+            if let Line::Entry {
+                key, value, raw, ..
+            } = line
+                && let Some(new_value) = replace(key, value)
+            {
+                *value = new_value;
+                *raw = None;
+            }
+        }
+    }
+
+    /// Top-level `(key, value)` pairs in file order.
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.lines.iter().filter_map(|line| match line {
+            Line::Entry {
+                section: None,
+                key,
+                value,
+                ..
+            } => Some((key.as_str(), value.as_str())),
+            _ => None,
+        })
+    }
+
+    /// Names of the `[section]`s, in file order and without repeats.
+    pub fn sections(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = Vec::new();
+        for line in &self.lines {
+            if let Line::Section { name, .. } = line
+                && !names.contains(&name.as_str())
+            {
+                names.push(name);
+            }
+        }
+        names
+    }
+
+    /// Value of `key` inside `[section]`; the last occurrence wins.
+    pub fn section_get(&self, section: &str, key: &str) -> Option<&str> {
+        self.lines.iter().rev().find_map(|line| match line {
+            Line::Entry {
+                section: Some(s),
+                key: k,
+                value,
+                ..
+            } if s == section && k == key => Some(value.as_str()),
+            _ => None,
+        })
+    }
+}
+
+impl fmt::Display for Npmrc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in &self.lines {
+            match line {
+                Line::Entry { raw: Some(raw), .. } => f.write_str(raw)?,
+                Line::Entry { key, value, .. } => write!(f, "{}={}", key, format_value(value))?,
+                Line::Section { raw, .. } => f.write_str(raw)?,
+                Line::Other(raw) => f.write_str(raw)?,
+            }
+            f.write_str(self.newline)?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_line(raw: &str, section: Option<&str>) -> Line {
+    let trimmed = raw.trim();
+    if trimmed.is_empty() || trimmed.starts_with(';') || trimmed.starts_with('#') {
+        return Line::Other(raw.to_string());
+    }
+    if let Some(name) = trimmed
+        .strip_prefix('[')
+        .and_then(|rest| rest.strip_suffix(']'))
+    {
+        return Line::Section {
+            name: name.trim().to_string(),
+            raw: raw.to_string(),
+        };
+    }
+    let Some((key, value)) = trimmed.split_once('=') else {
+        return Line::Other(raw.to_string());
+    };
+    Line::Entry {
+        section: section.map(str::to_string),
+        key: unquote(key.trim()),
+        value: unquote(value.trim()),
+        raw: Some(raw.to_string()),
+    }
+}
+
+/// Follows the `ini` package npm uses: quoted values are taken literally,
+/// unquoted values end at the first unescaped `;` or `#`.
+fn unquote(value: &str) -> String {
+    if let Some(quote) = value.chars().next().filter(|c| *c == '"' || *c == '\'')
+        && let Some(end) = value[1..].find(quote).map(|i| i + 1)
+    {
+        let rest = value[end + 1..].trim_start();
+        if rest.is_empty() || rest.starts_with(';') || rest.starts_with('#') {
+            let quoted = &value[..=end];
+            if quote == '"'
+                && let Ok(parsed) = serde_json::from_str::<String>(quoted)
+            {
+                return parsed;
+            }
+            return value[1..end].to_string();
+        }
+    }
+
+    let mut out = String::new();
+    let mut escaped = false;
+    for c in value.chars() {
+        if escaped {
+            if !matches!(c, '\\' | ';' | '#') {
+                out.push('\\');
+            }
+            out.push(c);
+            escaped = false;
+        } else if c == ';' || c == '#' {
+            break;
+        } else if c == '\\' {
+            escaped = true;
+        } else {
+            out.push(c);
+        }
+    }
+    if escaped {
+        out.push('\\');
+    }
+    out.trim().to_string()
+}
+
+fn format_value(value: &str) -> String {
+    if value.contains([';', '#']) || value != value.trim() {
+        serde_json::to_string(value).unwrap_or_else(|_| value.to_string())
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn untouched_lines_round_trip() {
+        let contents = "; comment\r\n# another\r\n\r\nregistry = https://registry.npmjs.org/ ; trailing\r\n\
+                        email=\"me@example.com\"\r\n[corp]\r\nregistry=https://npm.corp.example/\r\n";
+        assert_eq!(Npmrc::parse(contents).to_string(), contents);
+    }
+
+    #[test]
+    fn values_are_unquoted_like_ini() {
+        let npmrc = Npmrc::parse(concat!(
+            "a=plain ; comment\n",
+            "b=\"quoted ; kept\"\n",
+            "c='single # kept'\n",
+            "d=escaped \\; semicolon\n",
+            "\"e\"=\"\\u0041\"\n",
+            "f=C:\\path\n",
+        ));
+        assert_eq!(npmrc.get("a"), Some("plain"));
+        assert_eq!(npmrc.get("b"), Some("quoted ; kept"));
+        assert_eq!(npmrc.get("c"), Some("single # kept"));
+        assert_eq!(npmrc.get("d"), Some("escaped ; semicolon"));
+        assert_eq!(npmrc.get("e"), Some("A"));
+        assert_eq!(npmrc.get("f"), Some("C:\\path"));
+    }
+
+    #[test]
+    fn last_occurrence_wins_and_sections_are_separate() {
+        let npmrc = Npmrc::parse(
+            "registry=https://a.example/\nregistry=https://b.example/\n\
+             [nrm]\nregistry=https://c.example/\n[yrm]\nhome=https://d.example/\n",
+        );
+        assert_eq!(npmrc.get("registry"), Some("https://b.example/"));
+        assert_eq!(npmrc.sections(), ["nrm", "yrm"]);
+        assert_eq!(
+            npmrc.section_get("nrm", "registry"),
+            Some("https://c.example/")
+        );
+        assert_eq!(npmrc.section_get("yrm", "registry"), None);
+        assert_eq!(npmrc.entries().count(), 2);
+    }
+
+    #[test]
+    fn set_replaces_in_place_and_keeps_other_lines() {
+        let mut npmrc = Npmrc::parse(
+            "# top\nregistry=https://a.example/\nsave-exact=true\nregistry=https://b.example/\n",
+        );
+        npmrc.set("registry", "https://c.example/");
+        assert_eq!(
+            npmrc.to_string(),
+            "# top\nregistry=https://c.example/\nsave-exact=true\n"
+        );
+    }
+
+    #[test]
+    fn set_appends_new_keys_before_sections() {
+        let mut npmrc = Npmrc::parse("save-exact=true\r\n[nrm]\r\nregistry=https://a.example/\r\n");
+        npmrc.set("@acme:registry", "https://npm.acme.example/");
+        assert_eq!(
+            npmrc.to_string(),
+            "save-exact=true\r\n@acme:registry=https://npm.acme.example/\r\n\
+             [nrm]\r\nregistry=https://a.example/\r\n"
+        );
+    }
+
+    #[test]
+    fn set_quotes_values_that_would_not_survive() {
+        let mut npmrc = Npmrc::parse("");
+        npmrc.set("note", "a;b");
+        npmrc.set("padded", " x ");
+        let written = npmrc.to_string();
+        assert_eq!(written, "note=\"a;b\"\npadded=\" x \"\n");
+        let reread = Npmrc::parse(&written);
+        assert_eq!(reread.get("note"), Some("a;b"));
+        assert_eq!(reread.get("padded"), Some(" x "));
+    }
+
+    #[test]
+    fn remove_drops_every_top_level_occurrence() {
+        let mut npmrc = Npmrc::parse(
+            "registry=https://a.example/\n; keep me\nregistry=https://b.example/\n\
+             [nrm]\nregistry=https://c.example/\n",
+        );
+        assert!(npmrc.remove("registry"));
+        assert!(!npmrc.remove("registry"));
+        assert_eq!(
+            npmrc.to_string(),
+            "; keep me\n[nrm]\nregistry=https://c.example/\n"
+        );
+    }
+
+    #[test]
+    fn replace_values_keeps_positions() {
+        let mut npmrc = Npmrc::parse(
+            "//a.example/:_authToken=secret\nregistry=https://a.example/\n\
+             [nrm]\n_password=hunter2\n",
+        );
+        npmrc.replace_values(|key, _| {
+            (key.ends_with("_authToken") || key.ends_with("_password")).then(|| "***".to_string())
+        });
+        assert_eq!(
+            npmrc.to_string(),
+            "//a.example/:_authToken=***\nregistry=https://a.example/\n[nrm]\n_password=***\n"
+        );
+    }
+}
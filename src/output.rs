@@ -0,0 +1,182 @@
+use clap::ValueEnum;
+use colored::{ColoredString, Colorize};
+use serde::Serialize;
+
+use crate::{
+    config::{Config, Drift},
+    error::NrsError,
+    history::ProbeRecord,
+    probe::{Health, LatencyStats, ProbeResult, ProbeTarget},
+    registry::Origin,
+    utils::NpmrcTarget,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Coloured, human-friendly output
+    Table,
+    /// Stable JSON documents for scripting
+    Json,
+    /// Human-readable output without colours or emoji
+    Plain,
+    /// TOML, only for the registry set printed by nrs export
+    Toml,
+}
+
+impl OutputFormat {
+    pub fn is_json(self) -> bool {
+        self == OutputFormat::Json
+    }
+
+    /// Picks the decorated or the plain variant of a status marker.
+    pub fn icon(self, table: &'static str, plain: &'static str) -> &'static str {
+        match self {
+            OutputFormat::Table => table,
+            _ => plain,
+        }
+    }
+}
+
+pub fn print_json<T: Serialize>(value: &T) -> Result<(), NrsError> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct ErrorReport {
+    error: String,
+}
+
+/// Reports a failed command: `{error}` on stdout for JSON, a message on stderr otherwise.
+pub fn print_error(err: &NrsError, format: OutputFormat) {
+    if format.is_json()
+        && print_json(&ErrorReport {
+            error: err.to_string(),
+        })
+        .is_ok()
+    {
+        return;
+    }
+    eprintln!("{} {}", "Error:".red(), err);
+}
+
+/// JSON shape shared by every command that reports a registry.
+#[derive(Serialize)]
+pub struct RegistryInfo {
+    pub name: String,
+    pub url: String,
+    pub origin: Origin,
+    pub current: bool,
+    pub scopes: Vec<String>,
+    pub description: Option<String>,
+    pub home: Option<String>,
+    pub tags: Vec<String>,
+    pub keep: bool,
+    pub disabled: bool,
+    /// Most recent probe from the history, only reported by `nrs ls`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_probe: Option<ProbeRecord>,
+}
+
+impl RegistryInfo {
+    pub fn new(config: &Config, name: &str) -> Option<Self> {
+        let registry = config.get(name)?;
+        Some(RegistryInfo {
+            name: name.to_string(),
+            url: registry.url.clone(),
+            origin: registry.origin,
+            current: config.current.as_deref() == Some(name),
+            scopes: config
+                .scopes_for(name)
+                .into_iter()
+                .map(str::to_string)
+                .collect(),
+            description: registry.description.clone(),
+            home: registry.home.clone(),
+            tags: registry.tags.clone(),
+            keep: registry.keep,
+            disabled: registry.disabled,
+            last_probe: None,
+        })
+    }
+}
+
+/// JSON shape of a single reachability check.
+#[derive(Serialize)]
+pub struct ProbeReport {
+    pub name: Option<String>,
+    pub url: String,
+    pub strategy: String,
+    pub health: Health,
+    /// False only when the registry is down; auth-required still counts as reachable.
+    pub reachable: bool,
+    pub http_status: Option<u16>,
+    pub latency_ms: Option<u128>,
+    /// Present when probing with `--samples` greater than one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stats: Option<LatencyStats>,
+    /// Taken from the probe history instead of a new request (`nrs test --cached`).
+    pub cached: bool,
+}
+
+impl ProbeReport {
+    pub fn new(target: ProbeTarget, result: ProbeResult) -> Self {
+        ProbeReport {
+            name: target.name,
+            url: target.url,
+            strategy: target.strategy.to_string(),
+            health: result.health,
+            reachable: !result.health.is_down(),
+            http_status: result.http_status,
+            latency_ms: Some(result.latency_ms),
+            stats: result.stats,
+            cached: false,
+        }
+    }
+
+    pub fn from_record(target: ProbeTarget, record: &ProbeRecord) -> Self {
+        ProbeReport {
+            name: target.name,
+            url: target.url,
+            strategy: target.strategy.to_string(),
+            health: record.health,
+            reachable: !record.health.is_down(),
+            http_status: None,
+            latency_ms: Some(record.latency_ms),
+            stats: None,
+            cached: true,
+        }
+    }
+}
+
+/// Status text for a probe, e.g. `✅ up` or `🔒 auth required`.
+pub fn health_label(health: Health, format: OutputFormat) -> ColoredString {
+    let icon = match health {
+        Health::Up => format.icon("✅ ", ""),
+        Health::AuthRequired => format.icon("🔒 ", ""),
+        Health::NotARegistry => format.icon("⚠️  ", ""),
+        _ => format.icon("🔴 ", ""),
+    };
+    let label = format!("{}{}", icon, health.label());
+    match health {
+        Health::Up => label.green(),
+        Health::AuthRequired | Health::NotARegistry => label.yellow(),
+        _ => label.red(),
+    }
+}
+
+/// One-line description of a [`Drift`], e.g. for `current` and `doctor`.
+pub fn drift_text(drift: &Drift, target: NpmrcTarget) -> String {
+    match drift {
+        Drift::Known { name, url } => format!(
+            "{} uses {} ({}), not the registry selected in nrs",
+            target.label(),
+            name,
+            url
+        ),
+        Drift::Unknown { url } => {
+            format!("{} uses {}, which nrs doesn't know", target.label(), url)
+        }
+        Drift::Missing => format!("{} sets no registry", target.label()),
+    }
+}
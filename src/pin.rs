@@ -0,0 +1,123 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{config::normalize_scope, error::NrsError, share::SharedRegistry, utils::write_atomic};
+
+/// Pin file names, in lookup order within a directory.
+pub const PIN_FILES: [&str; 2] = [".nrs", ".nrsrc.json"];
+
+/// The registry and scope mappings a project needs, committed at its root
+/// like `.nvmrc`. Names refer to registries in `~/.nrsrc`.
+///
+/// `.nrsrc.json` holds this struct as JSON. `.nrs` is line based: a bare
+/// name or `registry=<name>` selects the registry, `@scope=<name>` maps a
+/// scope, `<name>=<url>` defines a registry, and `#` starts a comment.
+#[derive(Default, Serialize, Deserialize)]
+pub struct Pin {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub registry: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub scopes: BTreeMap<String, String>,
+    /// Registries the project defines for developers who lack them, in the
+    /// shape written by `nrs export`; their `scopes` join the pin's.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub registries: Vec<SharedRegistry>,
+}
+
+impl Pin {
+    /// Walks up from `start` to the nearest directory with a pin file.
+    pub fn find(start: &Path) -> Option<PathBuf> {
+        start.ancestors().find_map(|dir| {
+            PIN_FILES
+                .iter()
+                .map(|name| dir.join(name))
+                .find(|path| path.is_file())
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, NrsError> {
+        let contents = fs::read_to_string(path)?;
+        let invalid = |reason: String| NrsError::InvalidPin(path.to_path_buf(), reason);
+        if is_json(path) {
+            let mut pin: Pin =
+                serde_json::from_str(&contents).map_err(|err| invalid(err.to_string()))?;
+            let defined = pin.registries.iter().flat_map(|entry| {
+                entry
+                    .scopes
+                    .iter()
+                    .map(|scope| (scope.clone(), entry.name.clone()))
+            });
+            pin.scopes = pin
+                .scopes
+                .clone()
+                .into_iter()
+                .chain(defined)
+                .map(|(scope, name)| Ok((normalize_scope(&scope)?, name)))
+                .collect::<Result<_, NrsError>>()?;
+            for entry in &pin.registries {
+                if !entry.url.starts_with("http://") && !entry.url.starts_with("https://") {
+                    return Err(invalid(format!(
+                        "invalid URL for {}: {}",
+                        entry.name, entry.url
+                    )));
+                }
+            }
+            return Ok(pin);
+        }
+
+        let mut pin = Pin::default();
+        for line in contents.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            match line.split_once('=') {
+                None => pin.registry = Some(line.to_string()),
+                Some((key, name)) => {
+                    let (key, name) = (key.trim(), name.trim());
+                    if name.is_empty() {
+                        return Err(invalid(format!("no registry name for {}", key)));
+                    }
+                    if key == "registry" {
+                        pin.registry = Some(name.to_string());
+                    } else if key.starts_with('@') {
+                        pin.scopes.insert(normalize_scope(key)?, name.to_string());
+                    } else if name.starts_with("http://") || name.starts_with("https://") {
+                        pin.registries.push(SharedRegistry::new(key, name));
+                    } else {
+                        return Err(invalid(format!("unknown key {}", key)));
+                    }
+                }
+            }
+        }
+        Ok(pin)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), NrsError> {
+        let contents = if is_json(path) {
+            serde_json::to_string_pretty(self)? + "\n"
+        } else {
+            let mut contents = String::new();
+            if let Some(registry) = &self.registry {
+                contents.push_str(&format!("registry={}\n", registry));
+            }
+            for (scope, name) in &self.scopes {
+                contents.push_str(&format!("{}={}\n", scope, name));
+            }
+            for entry in &self.registries {
+                contents.push_str(&format!("{}={}\n", entry.name, entry.url));
+            }
+            contents
+        };
+        write_atomic(path, &contents)
+    }
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "json")
+}
@@ -0,0 +1,205 @@
+use serde::{Serialize, Serializer};
+
+use crate::registry::{Origin, Registry};
+
+/// A registry shipped with nrs. Presets live in the binary, so fixes and new
+/// entries reach users on upgrade; `.nrsrc` only records how a user departs
+/// from them.
+pub struct Preset {
+    pub name: &'static str,
+    pub url: &'static str,
+    pub description: &'static str,
+    pub home: &'static str,
+    /// The [`Pack`] the preset ships in.
+    pub pack: &'static str,
+    /// A URL pattern to fill in rather than a usable registry; shipped disabled.
+    pub template: bool,
+}
+
+/// A group of presets enabled or disabled as a whole with `nrs presets enable`.
+pub struct Pack {
+    pub name: &'static str,
+    pub description: &'static str,
+}
+
+pub const PACKS: &[Pack] = &[
+    Pack {
+        name: "global",
+        description: "The public npm registry and well-known proxies of it",
+    },
+    Pack {
+        name: "china",
+        description: "Mirrors hosted in mainland China",
+    },
+    Pack {
+        name: "enterprise-templates",
+        description: "URL patterns for Artifactory, Nexus, Verdaccio and GitLab",
+    },
+];
+
+/// Packs enabled in a new config.
+pub const DEFAULT_PACKS: &[&str] = &["global", "china"];
+
+pub const PRESETS: &[Preset] = &[
+    Preset {
+        name: "npm",
+        url: "https://registry.npmjs.org/",
+        description: "The public npm registry",
+        home: "https://www.npmjs.com/",
+        pack: "global",
+        template: false,
+    },
+    Preset {
+        name: "yarn",
+        url: "https://registry.yarnpkg.com/",
+        description: "Yarn's proxy of the public npm registry",
+        home: "https://yarnpkg.com/",
+        pack: "global",
+        template: false,
+    },
+    Preset {
+        name: "taobao",
+        url: "https://registry.npmmirror.com/",
+        description: "npmmirror, formerly the Taobao mirror (China)",
+        home: "https://npmmirror.com/",
+        pack: "china",
+        template: false,
+    },
+    Preset {
+        name: "tencent",
+        url: "https://mirrors.cloud.tencent.com/npm/",
+        description: "Tencent Cloud mirror (China)",
+        home: "https://mirrors.cloud.tencent.com/",
+        pack: "china",
+        template: false,
+    },
+    Preset {
+        name: "huawei",
+        url: "https://repo.huaweicloud.com/repository/npm/",
+        description: "Huawei Cloud mirror (China)",
+        home: "https://mirrors.huaweicloud.com/",
+        pack: "china",
+        template: false,
+    },
+    Preset {
+        name: "github",
+        url: "https://npm.pkg.github.com/",
+        description: "GitHub Packages, requires a token",
+        home: "https://github.com/features/packages",
+        pack: "global",
+        template: false,
+    },
+    Preset {
+        name: "artifactory",
+        url: "https://example.jfrog.io/artifactory/api/npm/npm/",
+        description: "JFrog Artifactory: replace the host and repository key",
+        home: "https://jfrog.com/artifactory/",
+        pack: "enterprise-templates",
+        template: true,
+    },
+    Preset {
+        name: "nexus",
+        url: "https://nexus.example.com/repository/npm-group/",
+        description: "Sonatype Nexus: replace the host and repository name",
+        home: "https://www.sonatype.com/products/sonatype-nexus-repository",
+        pack: "enterprise-templates",
+        template: true,
+    },
+    Preset {
+        name: "verdaccio",
+        url: "http://localhost:4873/",
+        description: "Verdaccio on its default port: replace the host if it runs elsewhere",
+        home: "https://verdaccio.org/",
+        pack: "enterprise-templates",
+        template: true,
+    },
+    Preset {
+        name: "gitlab",
+        url: "https://gitlab.example.com/api/v4/packages/npm/",
+        description: "GitLab instance-level endpoint: replace the host",
+        home: "https://docs.gitlab.com/user/packages/npm_registry/",
+        pack: "enterprise-templates",
+        template: true,
+    },
+];
+
+impl Preset {
+    pub fn registry(&self) -> Registry {
+        let mut registry = Registry::new(self.name, self.url, Origin::Builtin);
+        registry.description = Some(self.description.to_string());
+        registry.home = Some(self.home.to_string());
+        registry.disabled = self.template;
+        registry
+    }
+}
+
+pub fn default_packs() -> Vec<String> {
+    DEFAULT_PACKS.iter().map(|pack| pack.to_string()).collect()
+}
+
+pub fn find(name: &str) -> Option<&'static Preset> {
+    PRESETS.iter().find(|preset| preset.name == name)
+}
+
+pub fn find_pack(name: &str) -> Option<&'static Pack> {
+    PACKS.iter().find(|pack| pack.name == name)
+}
+
+/// Presets shipped in any of `packs`, in preset order.
+pub fn in_packs(packs: &[String]) -> impl Iterator<Item = &'static Preset> {
+    PRESETS
+        .iter()
+        .filter(move |preset| packs.iter().any(|pack| pack == preset.pack))
+}
+
+/// Lays the stored records over the presets of the enabled packs: presets
+/// come first, in preset order, unless removed, shadowed by a user registry
+/// of the same name, or overridden by a stored record; every other stored
+/// record follows. Overrides of presets that no longer ship, or whose pack is
+/// disabled, are kept as custom registries.
+pub fn merge(stored: Vec<Registry>, removed: &[String], packs: &[String]) -> Vec<Registry> {
+    let mut merged: Vec<Registry> = in_packs(packs)
+        .filter(|preset| !removed.iter().any(|name| name == preset.name))
+        .filter(|preset| {
+            !stored
+                .iter()
+                .any(|registry| registry.origin != Origin::Builtin && registry.name == preset.name)
+        })
+        .map(|preset| {
+            stored
+                .iter()
+                .find(|registry| registry.origin == Origin::Builtin && registry.name == preset.name)
+                .cloned()
+                .unwrap_or_else(|| preset.registry())
+        })
+        .collect();
+    merged.extend(stored.into_iter().filter_map(|mut registry| {
+        if registry.origin == Origin::Builtin {
+            if in_packs(packs).any(|preset| preset.name == registry.name) {
+                return None;
+            }
+            registry.origin = Origin::Custom;
+        }
+        Some(registry)
+    }));
+    merged
+}
+
+/// True when `registry` is a preset exactly as shipped, so it need not be stored.
+pub fn is_pristine(registry: &Registry) -> bool {
+    registry.origin == Origin::Builtin
+        && find(&registry.name).is_some_and(|preset| preset.registry() == *registry)
+}
+
+/// Writes only the records that differ from the presets: overrides and
+/// user-added registries. Used for `Config::registries`.
+pub fn serialize_overrides<S: Serializer>(
+    registries: &[Registry],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let stored: Vec<&Registry> = registries
+        .iter()
+        .filter(|registry| !is_pristine(registry) && registry.origin != Origin::Project)
+        .collect();
+    stored.serialize(serializer)
+}
@@ -0,0 +1,340 @@
+use std::{
+    error::Error,
+    fmt, io,
+    str::FromStr,
+    sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use reqwest::{
+    StatusCode,
+    blocking::{Client, Response},
+    header::ACCEPT,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::error::NrsError;
+
+/// Global probe settings from `--timeout` and `--jobs`.
+#[derive(Clone, Copy)]
+pub struct ProbeOptions {
+    pub timeout: Duration,
+    pub jobs: usize,
+}
+
+/// How a registry is checked.
+#[derive(Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum ProbeStrategy {
+    /// `GET <registry>/-/ping`, the npm ping endpoint.
+    #[default]
+    Ping,
+    /// `GET <registry>/<package>`, fetching a known package's metadata.
+    Package(String),
+    /// `HEAD <registry>`, which many registries reject even when healthy.
+    Head,
+}
+
+impl FromStr for ProbeStrategy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "ping" => Ok(ProbeStrategy::Ping),
+            "head" => Ok(ProbeStrategy::Head),
+            _ => match value.strip_prefix("package:") {
+                Some(package) if !package.is_empty() => {
+                    Ok(ProbeStrategy::Package(package.to_string()))
+                }
+                _ => Err(format!(
+                    "unknown probe strategy: {} (expected ping, head or package:<name>)",
+                    value
+                )),
+            },
+        }
+    }
+}
+
+impl TryFrom<String> for ProbeStrategy {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<ProbeStrategy> for String {
+    fn from(strategy: ProbeStrategy) -> Self {
+        strategy.to_string()
+    }
+}
+
+impl fmt::Display for ProbeStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProbeStrategy::Ping => f.write_str("ping"),
+            ProbeStrategy::Package(package) => write!(f, "package:{}", package),
+            ProbeStrategy::Head => f.write_str("head"),
+        }
+    }
+}
+
+/// Outcome of a probe, from healthy to the various ways of being down.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Health {
+    Up,
+    AuthRequired,
+    NotARegistry,
+    ServerError,
+    DnsFailure,
+    TlsFailure,
+    Timeout,
+    Unreachable,
+}
+
+impl Health {
+    /// The server could not be reached or did not answer sanely. Auth-required and
+    /// not-a-registry answers are deliberately excluded: the host is alive.
+    pub fn is_down(self) -> bool {
+        matches!(
+            self,
+            Health::ServerError
+                | Health::DnsFailure
+                | Health::TlsFailure
+                | Health::Timeout
+                | Health::Unreachable
+        )
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Health::Up => "up",
+            Health::AuthRequired => "auth required",
+            Health::NotARegistry => "not a registry",
+            Health::ServerError => "server error",
+            Health::DnsFailure => "DNS failure",
+            Health::TlsFailure => "TLS failure",
+            Health::Timeout => "timeout",
+            Health::Unreachable => "unreachable",
+        }
+    }
+}
+
+/// A registry to probe, with its effective timeout and strategy.
+pub struct ProbeTarget {
+    pub name: Option<String>,
+    pub url: String,
+    pub timeout: Duration,
+    pub strategy: ProbeStrategy,
+}
+
+pub struct ProbeResult {
+    pub health: Health,
+    pub http_status: Option<u16>,
+    /// The single sample, or the median when sampling several times.
+    pub latency_ms: u128,
+    pub stats: Option<LatencyStats>,
+}
+
+/// Latency spread over several samples taken on a warm connection.
+#[derive(Clone, Serialize)]
+pub struct LatencyStats {
+    pub samples: usize,
+    pub failures: usize,
+    /// The discarded warm-up request, which pays for DNS, TCP and TLS setup.
+    pub cold_ms: u128,
+    pub min_ms: u128,
+    pub median_ms: u128,
+    pub p95_ms: u128,
+    /// Mean absolute difference between consecutive samples.
+    pub jitter_ms: f64,
+}
+
+impl LatencyStats {
+    fn from_samples(cold_ms: u128, latencies: &[u128], failures: usize) -> Option<Self> {
+        if latencies.is_empty() {
+            return None;
+        }
+        let mut sorted = latencies.to_vec();
+        sorted.sort_unstable();
+        let percentile = |p: f64| {
+            let rank = ((p * sorted.len() as f64).ceil() as usize).clamp(1, sorted.len());
+            sorted[rank - 1]
+        };
+        let jitter_ms = if latencies.len() > 1 {
+            latencies
+                .windows(2)
+                .map(|pair| pair[0].abs_diff(pair[1]) as f64)
+                .sum::<f64>()
+                / (latencies.len() - 1) as f64
+        } else {
+            0.0
+        };
+        Some(LatencyStats {
+            samples: latencies.len() + failures,
+            failures,
+            cold_ms,
+            min_ms: sorted[0],
+            median_ms: percentile(0.5),
+            p95_ms: percentile(0.95),
+            jitter_ms,
+        })
+    }
+}
+
+/// Probes registries over one shared HTTP client.
+pub struct Prober {
+    client: Client,
+    jobs: usize,
+}
+
+impl Prober {
+    pub fn new(options: ProbeOptions) -> Result<Self, NrsError> {
+        let client = Client::builder().timeout(options.timeout).build()?;
+        Ok(Prober {
+            client,
+            jobs: options.jobs.max(1),
+        })
+    }
+
+    pub fn probe(&self, target: &ProbeTarget) -> ProbeResult {
+        let base = target.url.trim_end_matches('/');
+        let request = match &target.strategy {
+            ProbeStrategy::Ping => self.client.get(format!("{}/-/ping", base)),
+            ProbeStrategy::Package(package) => self
+                .client
+                .get(format!("{}/{}", base, package.replace('/', "%2f")))
+                .header(ACCEPT, "application/vnd.npm.install-v1+json"),
+            ProbeStrategy::Head => self.client.head(&target.url),
+        };
+        let start = Instant::now();
+        let response = request.timeout(target.timeout).send();
+        let latency_ms = start.elapsed().as_millis();
+        let health = classify(&response);
+        let http_status = response.as_ref().ok().map(|resp| resp.status().as_u16());
+        // Drain the body so the connection goes back to the pool for the next sample.
+        if let Ok(mut resp) = response {
+            let _ = resp.copy_to(&mut io::sink());
+        }
+        ProbeResult {
+            health,
+            http_status,
+            latency_ms,
+            stats: None,
+        }
+    }
+
+    /// Probes `samples` times after a warm-up request. Latency figures only
+    /// count samples that were not down.
+    pub fn sample(&self, target: &ProbeTarget, samples: usize) -> ProbeResult {
+        let warm_up = self.probe(target);
+        if samples <= 1 || warm_up.health.is_down() {
+            return warm_up;
+        }
+
+        let mut latencies = Vec::with_capacity(samples);
+        let mut failures = 0;
+        // Report the latest non-up outcome, if any sample had one.
+        let mut health = Health::Up;
+        let mut http_status = warm_up.http_status;
+        for _ in 0..samples {
+            let result = self.probe(target);
+            if result.health.is_down() {
+                failures += 1;
+            } else {
+                latencies.push(result.latency_ms);
+            }
+            if result.health != Health::Up {
+                health = result.health;
+                http_status = result.http_status;
+            }
+        }
+        let stats = LatencyStats::from_samples(warm_up.latency_ms, &latencies, failures);
+        ProbeResult {
+            health,
+            http_status,
+            latency_ms: stats
+                .as_ref()
+                .map(|stats| stats.median_ms)
+                .unwrap_or(warm_up.latency_ms),
+            stats,
+        }
+    }
+
+    /// Probes every target concurrently, at most `jobs` at a time, taking
+    /// `samples` measurements each. Results come back in the same order as `targets`.
+    pub fn probe_all(&self, targets: &[ProbeTarget], samples: usize) -> Vec<ProbeResult> {
+        let next = AtomicUsize::new(0);
+        let results = Mutex::new(Vec::with_capacity(targets.len()));
+        thread::scope(|scope| {
+            for _ in 0..self.jobs.min(targets.len()) {
+                scope.spawn(|| {
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(target) = targets.get(index) else {
+                            break;
+                        };
+                        let result = self.sample(target, samples);
+                        results.lock().unwrap().push((index, result));
+                    }
+                });
+            }
+        });
+        let mut results = results.into_inner().unwrap();
+        results.sort_by_key(|(index, _)| *index);
+        results.into_iter().map(|(_, result)| result).collect()
+    }
+}
+
+fn classify(response: &Result<Response, reqwest::Error>) -> Health {
+    let err = match response {
+        Ok(resp) => {
+            let status = resp.status();
+            return if status.is_success() {
+                Health::Up
+            } else if status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN {
+                Health::AuthRequired
+            } else if status.is_server_error() {
+                Health::ServerError
+            } else {
+                Health::NotARegistry
+            };
+        }
+        Err(err) => err,
+    };
+    if err.is_timeout() {
+        return Health::Timeout;
+    }
+
+    // reqwest doesn't expose DNS or TLS failures directly, so look at the causes.
+    let mut causes = String::new();
+    let mut source: Option<&dyn Error> = Some(err);
+    while let Some(cause) = source {
+        causes.push_str(&cause.to_string().to_lowercase());
+        causes.push('\n');
+        source = cause.source();
+    }
+    if [
+        "dns",
+        "failed to lookup",
+        "name or service not known",
+        "no such host",
+    ]
+    .iter()
+    .any(|needle| causes.contains(needle))
+    {
+        Health::DnsFailure
+    } else if ["certificate", "tls", "ssl", "handshake"]
+        .iter()
+        .any(|needle| causes.contains(needle))
+    {
+        Health::TlsFailure
+    } else {
+        Health::Unreachable
+    }
+}
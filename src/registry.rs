@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+
+use crate::probe::ProbeStrategy;
+
+/// Where a registry definition comes from.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Origin {
+    /// Shipped with nrs.
+    Builtin,
+    /// Added by the user.
+    Custom,
+    /// Defined by the current project and not stored in `.nrsrc`.
+    Project,
+}
+
+impl Origin {
+    pub fn label(self) -> &'static str {
+        match self {
+            Origin::Builtin => "builtin",
+            Origin::Custom => "custom",
+            Origin::Project => "project",
+        }
+    }
+}
+
+/// A registry and everything nrs knows about it.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct Registry {
+    pub name: String,
+    pub url: String,
+    pub origin: Origin,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Web page for humans, as opposed to the registry URL.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub home: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Probe timeout override, in milliseconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "is_default_probe")]
+    pub probe: ProbeStrategy,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    /// Never removed by `prune`.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub keep: bool,
+    /// Hidden from `use` and left out of `test` and `use --fastest`.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub disabled: bool,
+}
+
+impl Registry {
+    pub fn new(name: &str, url: &str, origin: Origin) -> Self {
+        Registry {
+            name: name.to_string(),
+            url: url.to_string(),
+            origin,
+            description: None,
+            home: None,
+            tags: Vec::new(),
+            timeout_ms: None,
+            probe: ProbeStrategy::default(),
+            notes: None,
+            keep: false,
+            disabled: false,
+        }
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+}
+
+fn is_default_probe(probe: &ProbeStrategy) -> bool {
+    *probe == ProbeStrategy::default()
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
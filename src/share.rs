@@ -0,0 +1,143 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{config::Config, error::NrsError, probe::ProbeStrategy, registry::Registry};
+
+/// Version of the shared registry file written by `nrs export`.
+pub const EXPORT_VERSION: u32 = 1;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum FileFormat {
+    Json,
+    Toml,
+}
+
+impl FileFormat {
+    /// Guesses the format from a file extension, defaulting to JSON.
+    pub fn for_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("toml") => FileFormat::Toml,
+            _ => FileFormat::Json,
+        }
+    }
+}
+
+/// A registry set meant to be shared between machines: registry records and
+/// their scopes, but never auth tokens, history or the current selection.
+#[derive(Serialize, Deserialize)]
+pub struct RegistrySet {
+    #[serde(default)]
+    pub version: u32,
+    #[serde(default)]
+    pub registries: Vec<SharedRegistry>,
+}
+
+/// A [`Registry`] without its origin, plus the scopes mapped to it.
+#[derive(PartialEq, Serialize, Deserialize)]
+pub struct SharedRegistry {
+    pub name: String,
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub home: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "is_default_probe")]
+    pub probe: ProbeStrategy,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub keep: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub disabled: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scopes: Vec<String>,
+}
+
+impl SharedRegistry {
+    pub fn new(name: &str, url: &str) -> Self {
+        SharedRegistry {
+            name: name.to_string(),
+            url: url.to_string(),
+            description: None,
+            home: None,
+            tags: Vec::new(),
+            timeout_ms: None,
+            probe: ProbeStrategy::default(),
+            notes: None,
+            keep: false,
+            disabled: false,
+            scopes: Vec::new(),
+        }
+    }
+
+    pub fn from_registry(config: &Config, registry: &Registry) -> Self {
+        SharedRegistry {
+            name: registry.name.clone(),
+            url: registry.url.clone(),
+            description: registry.description.clone(),
+            home: registry.home.clone(),
+            tags: registry.tags.clone(),
+            timeout_ms: registry.timeout_ms,
+            probe: registry.probe.clone(),
+            notes: registry.notes.clone(),
+            keep: registry.keep,
+            disabled: registry.disabled,
+            scopes: config
+                .scopes_for(&registry.name)
+                .into_iter()
+                .map(str::to_string)
+                .collect(),
+        }
+    }
+
+    /// Copies everything but the name, URL and scopes onto `registry`.
+    pub fn apply_details(&self, registry: &mut Registry) {
+        registry.description = self.description.clone();
+        registry.home = self.home.clone();
+        registry.tags = self.tags.clone();
+        registry.timeout_ms = self.timeout_ms;
+        registry.probe = self.probe.clone();
+        registry.notes = self.notes.clone();
+        registry.keep = self.keep;
+        registry.disabled = self.disabled;
+    }
+}
+
+impl RegistrySet {
+    pub fn to_string(&self, format: FileFormat) -> Result<String, NrsError> {
+        match format {
+            FileFormat::Json => Ok(serde_json::to_string_pretty(self)?),
+            FileFormat::Toml => toml::to_string_pretty(self)
+                .map_err(|err| NrsError::InvalidRegistrySet(err.to_string())),
+        }
+    }
+
+    pub fn parse(contents: &str, format: FileFormat) -> Result<Self, NrsError> {
+        let set: RegistrySet = match format {
+            FileFormat::Json => serde_json::from_str(contents)
+                .map_err(|err| NrsError::InvalidRegistrySet(err.to_string()))?,
+            FileFormat::Toml => toml::from_str(contents)
+                .map_err(|err| NrsError::InvalidRegistrySet(err.to_string()))?,
+        };
+        if set.version > EXPORT_VERSION {
+            return Err(NrsError::InvalidRegistrySet(format!(
+                "version {} is newer than this nrs supports",
+                set.version
+            )));
+        }
+        Ok(set)
+    }
+}
+
+fn is_default_probe(probe: &ProbeStrategy) -> bool {
+    *probe == ProbeStrategy::default()
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
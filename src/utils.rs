@@ -1,13 +1,14 @@
-use crate::error::NrsError;
+use crate::{error::NrsError, npmrc::Npmrc};
 use dirs::home_dir;
 use std::{
-    collections::HashMap,
-    fs::File,
-    io::{BufRead, BufReader},
-    path::PathBuf,
+    fs::{self, File, TryLockError},
+    io::Write,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
-pub fn derive_registry_name(url: &str, custom_registries: &HashMap<String, String>) -> String {
+pub fn derive_registry_name(url: &str, is_taken: impl Fn(&str) -> bool) -> String {
     let host = url
         .trim_start_matches("http://")
         .trim_start_matches("https://")
@@ -16,58 +17,325 @@ pub fn derive_registry_name(url: &str, custom_registries: &HashMap<String, Strin
         .unwrap_or("unknown")
         .replace(['.', ':'], "-");
 
-    if !custom_registries.contains_key(&host) {
+    if !is_taken(&host) {
         return host;
     }
 
     let mut counter = 1;
     loop {
         let name = format!("{}-{}", host, counter);
-        if !custom_registries.contains_key(&name) {
+        if !is_taken(&name) {
             return name;
         }
         counter += 1;
     }
 }
 
+/// npm's per-registry credential key, e.g. `//npm.pkg.github.com/:_authToken`.
+pub fn auth_key(url: &str) -> String {
+    let path = url
+        .trim_start_matches("http://")
+        .trim_start_matches("https://")
+        .trim_end_matches('/');
+    format!("//{}/:_authToken", path)
+}
+
 pub fn get_config_path() -> Result<PathBuf, NrsError> {
     let home = home_dir().ok_or(NrsError::HomeDirNotFound)?;
     Ok(home.join(".nrsrc"))
 }
 
+/// How long to wait for another nrs process before giving up with [`NrsError::Locked`].
+const LOCK_WAIT: Duration = Duration::from_secs(5);
+
+/// Takes the advisory lock on `~/.nrsrc.lock`, waiting briefly if another nrs
+/// process holds it. The lock is released when the returned file is dropped.
+pub fn lock_config() -> Result<File, NrsError> {
+    let lock_path = get_config_path()?.with_file_name(".nrsrc.lock");
+    let file = File::options()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)?;
+    let start = Instant::now();
+    loop {
+        match file.try_lock() {
+            Ok(()) => return Ok(file),
+            Err(TryLockError::WouldBlock) if start.elapsed() < LOCK_WAIT => {
+                thread::sleep(Duration::from_millis(50));
+            }
+            Err(TryLockError::WouldBlock) => return Err(NrsError::Locked(lock_path)),
+            Err(TryLockError::Error(err)) => return Err(err.into()),
+        }
+    }
+}
+
+/// Replaces `path` with `contents` through a temporary file and a rename, so
+/// readers never see a half-written file. Keeps the permissions of the file
+/// being replaced and writes through symlinks, e.g. a dotfiles-managed `.npmrc`.
+pub fn write_atomic(path: &Path, contents: &str) -> Result<(), NrsError> {
+    replace_file(path, contents, false)
+}
+
+/// Like [`write_atomic`], but the file is only readable by its owner, for
+/// files that hold tokens.
+pub fn write_private(path: &Path, contents: &str) -> Result<(), NrsError> {
+    replace_file(path, contents, true)
+}
+
+fn replace_file(path: &Path, contents: &str, private: bool) -> Result<(), NrsError> {
+    let path = match fs::canonicalize(path) {
+        Ok(resolved) => resolved,
+        Err(_) => path.to_path_buf(),
+    };
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let temp_path = path.with_file_name(format!(".{}.{}.tmp", file_name, std::process::id()));
+
+    // The temporary file is created with its final mode, so the contents are
+    // never readable by more users than the file being replaced allows.
+    let mut options = File::options();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        let mode = fs::metadata(&path).map_or(0o666, |metadata| metadata.permissions().mode());
+        options.mode(if private { mode & 0o600 } else { mode & 0o7777 });
+    }
+    #[cfg(not(unix))]
+    let _ = private;
+
+    let result = (|| {
+        // A leftover from a crashed run with the same pid would keep its old mode.
+        let _ = fs::remove_file(&temp_path);
+        let mut file = options.open(&temp_path)?;
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&temp_path, &path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    Ok(result?)
+}
+
+/// Probe history, kept next to `.nrsrc`.
+pub fn get_history_path() -> Result<PathBuf, NrsError> {
+    Ok(get_config_path()?.with_file_name(".nrsrc-history.json"))
+}
+
+/// Parses durations such as `500ms`, `10s`, `5m`, `2h` or `1d`; a bare number means seconds.
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let (amount, unit) = value.split_at(split);
+    let amount: f64 = amount
+        .parse()
+        .map_err(|_| format!("invalid duration: {}", value))?;
+    let seconds = match unit.trim() {
+        "ms" => amount / 1000.0,
+        "" | "s" => amount,
+        "m" => amount * 60.0,
+        "h" => amount * 3600.0,
+        "d" => amount * 86400.0,
+        other => return Err(format!("unknown duration unit: {}", other)),
+    };
+    Ok(Duration::from_secs_f64(seconds))
+}
+
+/// Seconds since the Unix epoch.
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+/// Which npm config file a command reads or writes.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum NpmrcTarget {
+    /// The user config, `~/.npmrc` unless `NPM_CONFIG_USERCONFIG` says otherwise.
+    User,
+    /// The `.npmrc` at the project root.
+    Project,
+    /// The global config, `$PREFIX/etc/npmrc`.
+    Global,
+}
+
+impl NpmrcTarget {
+    pub fn from_flags(local: bool, global_npmrc: bool) -> Self {
+        if local {
+            NpmrcTarget::Project
+        } else if global_npmrc {
+            NpmrcTarget::Global
+        } else {
+            NpmrcTarget::User
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            NpmrcTarget::User => ".npmrc (global)",
+            NpmrcTarget::Project => ".npmrc (local)",
+            NpmrcTarget::Global => "npmrc (global config)",
+        }
+    }
+}
+
+/// Reads an npm config value from the environment. npm matches the
+/// `npm_config_` prefix case-insensitively.
+pub fn npm_config_env(key: &str) -> Option<String> {
+    npm_config_var(key).map(|(_, value)| value)
+}
+
+/// Like [`npm_config_env`], but also returns the variable name as spelled in the environment.
+pub fn npm_config_var(key: &str) -> Option<(String, String)> {
+    let wanted = format!("npm_config_{}", key);
+    // Variables that aren't valid UTF-8 can't be npm settings nrs understands.
+    std::env::vars_os()
+        .filter_map(|(name, value)| Some((name.into_string().ok()?, value.into_string().ok()?)))
+        .find(|(name, value)| name.eq_ignore_ascii_case(&wanted) && !value.is_empty())
+}
+
+fn expand_home(path: &str) -> Result<PathBuf, NrsError> {
+    match path.strip_prefix("~/").or_else(|| path.strip_prefix("~\\")) {
+        Some(rest) => Ok(home_dir().ok_or(NrsError::HomeDirNotFound)?.join(rest)),
+        None => Ok(PathBuf::from(path)),
+    }
+}
+
 pub fn get_npmrc_path() -> Result<PathBuf, NrsError> {
+    if let Some(path) = npm_config_env("userconfig") {
+        return expand_home(&path);
+    }
     let home = home_dir().ok_or(NrsError::HomeDirNotFound)?;
     Ok(home.join(".npmrc"))
 }
 
-pub fn get_npmrc_path_local(local: bool) -> Result<PathBuf, NrsError> {
-    if !local {
-        get_npmrc_path()
-    } else {
-        Ok(std::env::current_dir()?.join(".npmrc"))
+/// npm's local prefix: the nearest ancestor with a `package.json` or
+/// `node_modules`, lifted to the enclosing workspace root when one of its
+/// `workspaces` globs includes that package.
+pub fn get_project_root() -> Result<PathBuf, NrsError> {
+    let cwd = std::env::current_dir()?;
+    let Some(project) = cwd
+        .ancestors()
+        .find(|dir| dir.join("package.json").is_file() || dir.join("node_modules").is_dir())
+    else {
+        return Ok(cwd);
+    };
+    let workspace_root = project
+        .ancestors()
+        .skip(1)
+        .find(|dir| is_workspace_of(dir, project));
+    Ok(workspace_root.unwrap_or(project).to_path_buf())
+}
+
+/// Whether the `workspaces` of the `package.json` in `root` (an array of
+/// globs, or Yarn's `{"packages": [...]}`) include the package in `dir`.
+/// Later `!` globs exclude what earlier ones included.
+fn is_workspace_of(root: &Path, dir: &Path) -> bool {
+    let Some(package) = fs::read_to_string(root.join("package.json"))
+        .ok()
+        .and_then(|contents| serde_json::from_str::<serde_json::Value>(&contents).ok())
+    else {
+        return false;
+    };
+    let workspaces = package.get("workspaces");
+    let Some(globs) = workspaces
+        .and_then(|workspaces| workspaces.get("packages"))
+        .or(workspaces)
+        .and_then(serde_json::Value::as_array)
+    else {
+        return false;
+    };
+    let Ok(relative) = dir.strip_prefix(root) else {
+        return false;
+    };
+    let segments: Vec<&str> = relative
+        .components()
+        .filter_map(|component| component.as_os_str().to_str())
+        .collect();
+    let mut included = false;
+    for glob in globs.iter().filter_map(serde_json::Value::as_str) {
+        let (negated, glob) = match glob.strip_prefix('!') {
+            Some(glob) => (true, glob),
+            None => (false, glob),
+        };
+        let pattern: Vec<&str> = glob
+            .trim_start_matches("./")
+            .split('/')
+            .filter(|segment| !segment.is_empty() && *segment != ".")
+            .collect();
+        if glob_matches(&pattern, &segments) {
+            included = !negated;
+        }
     }
+    included
 }
 
-pub fn get_local_registry_url() -> Result<Option<String>, NrsError> {
-    let npmrc_path = get_npmrc_path_local(true)?;
-    if !npmrc_path.exists() {
-        return Ok(None);
+/// Matches path segments against glob segments, where `**` spans any number
+/// of segments.
+fn glob_matches(pattern: &[&str], path: &[&str]) -> bool {
+    match (pattern.split_first(), path.split_first()) {
+        (None, None) => true,
+        (Some((&"**", rest)), _) => {
+            glob_matches(rest, path) || (!path.is_empty() && glob_matches(pattern, &path[1..]))
+        }
+        (Some((segment, rest)), Some((name, path_rest))) => {
+            segment_matches(segment, name) && glob_matches(rest, path_rest)
+        }
+        _ => false,
     }
+}
 
-    let file = File::open(&npmrc_path)?;
-    let reader = BufReader::new(file);
+/// Matches one path segment, where `*` stands for any run of characters.
+fn segment_matches(pattern: &str, name: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == name,
+        Some((prefix, rest)) => name.strip_prefix(prefix).is_some_and(|name| {
+            (0..=name.len())
+                .filter(|index| name.is_char_boundary(*index))
+                .any(|index| segment_matches(rest, &name[index..]))
+        }),
+    }
+}
 
-    for line in reader.lines() {
-        let line = line?;
-        if line.trim().starts_with("registry=") {
-            let url = line
-                .trim()
-                .strip_prefix("registry=")
-                .unwrap_or("")
-                .to_string();
-            return Ok(Some(url));
-        }
+/// npm's `prefix`: `NPM_CONFIG_PREFIX`, or derived from the `node` binary on `PATH`.
+pub fn get_npm_prefix() -> Option<PathBuf> {
+    if let Some(prefix) = npm_config_env("prefix") {
+        return Some(PathBuf::from(prefix));
+    }
+    let node = if cfg!(windows) { "node.exe" } else { "node" };
+    let node_dir =
+        std::env::split_paths(&std::env::var_os("PATH")?).find(|dir| dir.join(node).is_file())?;
+    if cfg!(windows) {
+        Some(node_dir)
+    } else {
+        node_dir.parent().map(Path::to_path_buf)
     }
+}
+
+pub fn get_global_npmrc_path() -> Result<PathBuf, NrsError> {
+    if let Some(path) = npm_config_env("globalconfig") {
+        return expand_home(&path);
+    }
+    let prefix = get_npm_prefix().unwrap_or_else(|| PathBuf::from("/usr/local"));
+    Ok(prefix.join("etc").join("npmrc"))
+}
+
+pub fn get_npmrc_path_for(target: NpmrcTarget) -> Result<PathBuf, NrsError> {
+    match target {
+        NpmrcTarget::User => get_npmrc_path(),
+        NpmrcTarget::Project => Ok(get_project_root()?.join(".npmrc")),
+        NpmrcTarget::Global => get_global_npmrc_path(),
+    }
+}
 
-    Ok(None)
+pub fn get_registry_url(target: NpmrcTarget) -> Result<Option<String>, NrsError> {
+    let npmrc = Npmrc::load(&get_npmrc_path_for(target)?)?;
+    Ok(npmrc.get("registry").map(str::to_string))
 }